@@ -0,0 +1,146 @@
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll};
+use rand::Rng;
+use tokio_timer::Delay;
+
+use super::{fetch_static_enodes, DiscoveryFailurePolicy, DiscoveryParameters, EthereumNodeUrl};
+use super::{EnodeResolver, RegistryClient};
+use super::{Error, ErrorKind};
+
+type StaticEnodesFetcher = Box<Future<Item = Vec<EthereumNodeUrl>, Error = Error> + Send>;
+
+/// fetch static enodes until at least `min_peers` are found, retry with exponential backoff
+/// until deadline, then fail or continue with the most enodes fetched so far
+pub struct StaticEnodeDiscovery {
+    registry_client: RegistryClient,
+    resolver: EnodeResolver,
+    network_name: String,
+    genesis_inputs_hash: String,
+    accept_missing_genesis_inputs_hash: bool,
+    parameters: DiscoveryParameters,
+
+    attempts: usize,
+    backoff: Duration,
+    fetcher: Option<StaticEnodesFetcher>,
+    retry_delay: Option<Delay>,
+    deadline: Delay,
+
+    /// most static enodes fetched so far
+    best: Vec<EthereumNodeUrl>,
+}
+
+impl StaticEnodeDiscovery {
+    pub fn new(
+        registry_client: RegistryClient,
+        network_name: String,
+        genesis_inputs_hash: String,
+        accept_missing_genesis_inputs_hash: bool,
+        parameters: DiscoveryParameters,
+    ) -> StaticEnodeDiscovery {
+        StaticEnodeDiscovery {
+            registry_client,
+            resolver: EnodeResolver::new(),
+            network_name,
+            genesis_inputs_hash,
+            accept_missing_genesis_inputs_hash,
+            parameters,
+
+            attempts: 0,
+            backoff: parameters.initial_backoff,
+            fetcher: None,
+            retry_delay: None,
+            deadline: Delay::new(Instant::now() + parameters.deadline),
+
+            best: Vec::new(),
+        }
+    }
+
+    /// backoff of next retry with jitter, between half and full backoff
+    fn next_backoff(&mut self) -> Duration {
+        let backoff = self.backoff;
+        self.backoff = std::cmp::min(backoff * 2, self.parameters.max_backoff);
+
+        let millis = backoff.as_secs() * 1000 + u64::from(backoff.subsec_millis());
+        let jitter = rand::thread_rng().gen_range(0, millis / 2 + 1);
+        Duration::from_millis(millis - jitter)
+    }
+
+    fn give_up(&mut self) -> Poll<Vec<EthereumNodeUrl>, Error> {
+        let found = self.best.len();
+        match self.parameters.on_failure {
+            DiscoveryFailurePolicy::Fail => Err(Error::from(ErrorKind::StaticEnodeDiscovery(
+                found,
+                self.parameters.min_peers,
+                self.parameters.deadline,
+            ))),
+            DiscoveryFailurePolicy::Continue => {
+                warn!(target: "bootnode",
+                      "found {} of {} static enodes within {:?} after {} attempts, start Ethereum client anyway",
+                      found, self.parameters.min_peers, self.parameters.deadline, self.attempts);
+                Ok(Async::Ready(std::mem::replace(&mut self.best, Vec::new())))
+            }
+        }
+    }
+}
+
+impl Future for StaticEnodeDiscovery {
+    type Item = Vec<EthereumNodeUrl>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Error> {
+        loop {
+            if let Async::Ready(_) = self.deadline.poll()? {
+                return self.give_up();
+            }
+
+            if let Some(ref mut delay) = self.retry_delay {
+                if let Async::NotReady = delay.poll()? {
+                    return Ok(Async::NotReady);
+                }
+            }
+            self.retry_delay = None;
+
+            if self.fetcher.is_none() {
+                self.attempts += 1;
+                info!(target: "bootnode", "fetch static enodes, attempt {}", self.attempts);
+                self.fetcher = Some(Box::new(fetch_static_enodes(
+                    &self.registry_client,
+                    &self.resolver,
+                    &self.network_name,
+                    &self.genesis_inputs_hash,
+                    self.accept_missing_genesis_inputs_hash,
+                )));
+            }
+
+            let result = match self.fetcher {
+                Some(ref mut fetcher) => match fetcher.poll() {
+                    Ok(Async::Ready(nodes)) => Ok(nodes),
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(err) => Err(err),
+                },
+                None => unreachable!("fetcher is created above; qed"),
+            };
+            self.fetcher = None;
+
+            match result {
+                Ok(nodes) => {
+                    if nodes.len() >= self.parameters.min_peers {
+                        info!(target: "bootnode", "found {} static enodes", nodes.len());
+                        return Ok(Async::Ready(nodes));
+                    }
+                    info!(target: "bootnode", "found {} of {} static enodes",
+                          nodes.len(), self.parameters.min_peers);
+                    if nodes.len() > self.best.len() {
+                        self.best = nodes;
+                    }
+                }
+                Err(err) => warn!(target: "bootnode", "failed to fetch static enodes: {}", err),
+            }
+
+            let backoff = self.next_backoff();
+            info!(target: "bootnode", "retry fetching static enodes in {:?}", backoff);
+            self.retry_delay = Some(Delay::new(Instant::now() + backoff));
+        }
+    }
+}
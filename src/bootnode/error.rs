@@ -1,10 +1,14 @@
 error_chain! {
     foreign_links {
+        StdIo(std::io::Error);
         AddrParse(std::net::AddrParseError);
         Hyper(hyper::Error);
         Timer(tokio_timer::Error);
         Json(serde_json::Error);
         UrlParse(url::ParseError);
+        Http(hyper::http::Error);
+        Tls(native_tls::Error);
+        EthKey(ethkey::Error);
     }
 
     errors {
@@ -16,5 +20,25 @@ error_chain! {
             description("JSON RPC Error")
             display("JSON RPC Error: {:?}", t)
         }
+        StaticEnodeDiscovery(found: usize, min_peers: usize, deadline: std::time::Duration) {
+            description("Not enough static enodes")
+            display("Found {} of {} static enodes within {:?}", found, min_peers, deadline)
+        }
+        UnexpectedJsonRpcResult(method: String, result: String) {
+            description("Unexpected JSON RPC result")
+            display("Unexpected result of JSON RPC {}: {}", method, result)
+        }
+        RegistryRejected(status: u16, reason: String) {
+            description("Bootnode service rejected request")
+            display("Bootnode service rejected request with status {}: {}", status, reason)
+        }
+        InvalidCaBundle(path: String) {
+            description("Invalid CA bundle")
+            display("No PEM certificate found in CA bundle {}", path)
+        }
+        RegistryTimeout(timeout: std::time::Duration) {
+            description("Bootnode service did not respond in time")
+            display("Bootnode service did not respond within {:?}", timeout)
+        }
     }
 }
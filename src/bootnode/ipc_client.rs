@@ -0,0 +1,113 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::{Async, Future, Poll};
+use jsonrpc_core::response::Output as JsonRpcOutput;
+use serde_json::Value as JsonValue;
+use tokio::io::AsyncRead;
+use tokio::net::UnixStream;
+
+use super::jsonrpc_client::{extract_result, method_call, parse_enode_url};
+use super::{Error, ErrorKind};
+use super::{EthereumNodeUrl, EthereumProgram};
+
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// JSON RPC client over IPC socket of Ethereum client, for APIs which are not exposed over HTTP
+pub struct IpcClient {
+    path: PathBuf,
+    counter: AtomicUsize,
+}
+
+impl IpcClient {
+    pub fn new(path: PathBuf) -> IpcClient {
+        IpcClient {
+            path,
+            counter: AtomicUsize::default(),
+        }
+    }
+
+    /// send one request over a new connection
+    pub fn request(
+        &self,
+        method: &'static str,
+        params: Vec<JsonValue>,
+    ) -> impl Future<Item = JsonRpcOutput, Error = Error> {
+        let id = self.counter.fetch_add(1, Ordering::Relaxed);
+        let serialized = method_call(id, method, params);
+
+        UnixStream::connect(&self.path)
+            .and_then(move |stream| tokio::io::write_all(stream, serialized.into_bytes()))
+            .from_err::<Error>()
+            .and_then(|(stream, _)| ReadOutput {
+                stream,
+                buffer: Vec::new(),
+            })
+    }
+
+    /// add a peer to the running client, `parity_addReservedPeer` or `admin_addPeer`
+    pub fn add_peer(
+        &self,
+        program: EthereumProgram,
+        enode_url: &EthereumNodeUrl,
+    ) -> impl Future<Item = bool, Error = Error> {
+        let (method, enode_url) = match program {
+            EthereumProgram::Parity => {
+                ("parity_addReservedPeer", enode_url.without_discovery_port())
+            }
+            EthereumProgram::GoEthereum => ("admin_addPeer", enode_url.clone()),
+        };
+
+        self.request(method, vec![json!(enode_url.to_string())])
+            .and_then(|data| Ok(serde_json::from_value(extract_result(data)?)?))
+    }
+
+    /// enode URL of go-ethereum, `admin` API is only exposed over IPC
+    pub fn admin_node_info(&self) -> impl Future<Item = EthereumNodeUrl, Error = Error> {
+        self.request("admin_nodeInfo", vec![]).and_then(|data| {
+            let node_info = extract_result(data)?;
+            match node_info["enode"].as_str() {
+                Some(url) => parse_enode_url("admin_nodeInfo", url),
+                None => Err(Error::from(ErrorKind::UnexpectedJsonRpcResult(
+                    "admin_nodeInfo".to_owned(),
+                    node_info.to_string(),
+                ))),
+            }
+        })
+    }
+}
+
+/// read until a whole JSON RPC response is received, responses are not delimited
+struct ReadOutput {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+}
+
+impl Future for ReadOutput {
+    type Item = JsonRpcOutput;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<JsonRpcOutput, Error> {
+        loop {
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            let n = match self.stream.poll_read(&mut chunk)? {
+                Async::Ready(n) => n,
+                Async::NotReady => return Ok(Async::NotReady),
+            };
+            if n == 0 {
+                return Err(Error::from(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "IPC connection closed before response",
+                )));
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+
+            match serde_json::from_slice(&self.buffer) {
+                Ok(output) => return Ok(Async::Ready(output)),
+                Err(ref err) if err.is_eof() => continue,
+                Err(err) => return Err(Error::from(err)),
+            }
+        }
+    }
+}
@@ -35,14 +35,7 @@ impl JsonRpcClient {
         params: Vec<JsonValue>,
     ) -> impl Future<Item = JsonRpcOutput, Error = Error> {
         let id = self.counter.fetch_add(1, Ordering::Relaxed);
-        let method_call = MethodCall {
-            jsonrpc: Some(Version::V2),
-            method: method.to_owned(),
-            params: Params::Array(params),
-            id: Id::Num(id as u64),
-        };
-
-        let serialized = serde_json::to_string(&method_call).expect("request is serializable; qed");
+        let serialized = method_call(id, method, params);
         let request = Request::post(&self.host)
             .header("Content-Type", "application/json")
             .body(serialized.into())
@@ -58,19 +51,90 @@ impl JsonRpcClient {
 
     pub fn parity_enode(&self) -> impl Future<Item = EthereumNodeUrl, Error = Error> {
         self.request("parity_enode", vec![]).and_then(|data| {
-            use url::Url;
-            let url = {
-                let url: String = serde_json::from_value(extract_result(data)?)?;
-                Url::parse(url.as_str())?
-            };
-
-            Ok(EthereumNodeUrl {
-                node_id: url.username().to_owned(),
-                addr: url.host_str().unwrap().parse()?,
-                port: url.port().unwrap(),
-            })
+            let url: String = serde_json::from_value(extract_result(data)?)?;
+            parse_enode_url("parity_enode", url.as_str())
+        })
+    }
+
+    pub fn eth_block_number(&self) -> impl Future<Item = u64, Error = Error> {
+        self.request("eth_blockNumber", vec![])
+            .and_then(|data| parse_quantity("eth_blockNumber", extract_result(data)?))
+    }
+
+    /// transactions in pending block, supported by both Parity and go-ethereum
+    pub fn pending_transaction_count(&self) -> impl Future<Item = u64, Error = Error> {
+        self.request(
+            "eth_getBlockTransactionCountByNumber",
+            vec![json!("pending")],
+        )
+        .and_then(|data| {
+            parse_quantity(
+                "eth_getBlockTransactionCountByNumber",
+                extract_result(data)?,
+            )
         })
     }
+
+    pub fn net_peer_count(&self) -> impl Future<Item = u64, Error = Error> {
+        self.request("net_peerCount", vec![])
+            .and_then(|data| parse_quantity("net_peerCount", extract_result(data)?))
+    }
+
+    /// `eth_syncing` is `false` if the client is not syncing, or an object of sync progress
+    pub fn eth_syncing(&self) -> impl Future<Item = bool, Error = Error> {
+        self.request("eth_syncing", vec![])
+            .and_then(|data| match extract_result(data)? {
+                JsonValue::Bool(false) => Ok(false),
+                _ => Ok(true),
+            })
+    }
+
+    /// timestamp of the latest block, in seconds since UNIX epoch
+    pub fn latest_block_timestamp(&self) -> impl Future<Item = u64, Error = Error> {
+        self.request("eth_getBlockByNumber", vec![json!("latest"), json!(false)])
+            .and_then(|data| {
+                let block = extract_result(data)?;
+                parse_quantity("eth_getBlockByNumber", block["timestamp"].clone())
+            })
+    }
+}
+
+/// parse a hex encoded quantity of JSON RPC result
+fn parse_quantity(method: &str, value: JsonValue) -> Result<u64, Error> {
+    let invalid = || {
+        Error::from(ErrorKind::UnexpectedJsonRpcResult(
+            method.to_owned(),
+            value.to_string(),
+        ))
+    };
+
+    match value {
+        JsonValue::String(ref s) if s.starts_with("0x") => {
+            u64::from_str_radix(&s[2..], 16).map_err(|_| invalid())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// parse enode URL returned by JSON RPC `method`
+pub fn parse_enode_url(method: &str, value: &str) -> Result<EthereumNodeUrl, Error> {
+    value.parse().map_err(|_| {
+        Error::from(ErrorKind::UnexpectedJsonRpcResult(
+            method.to_owned(),
+            value.to_owned(),
+        ))
+    })
+}
+
+/// serialized JSON RPC request
+pub fn method_call(id: usize, method: &str, params: Vec<JsonValue>) -> String {
+    let method_call = MethodCall {
+        jsonrpc: Some(Version::V2),
+        method: method.to_owned(),
+        params: Params::Array(params),
+        id: Id::Num(id as u64),
+    };
+    serde_json::to_string(&method_call).expect("request is serializable; qed")
 }
 
 pub fn extract_result(value: JsonRpcOutput) -> Result<JsonValue, Error> {
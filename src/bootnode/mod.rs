@@ -1,78 +1,220 @@
+mod discovery;
 mod error;
+mod ipc_client;
 mod jsonrpc_client;
+mod peers;
+mod registry_client;
+mod resolver;
 mod service;
 
-use futures::{Future, Stream};
-use hyper::{Body, Client, Request, Uri};
+use futures::Future;
+use hyper::{Method, StatusCode};
 
+pub use self::discovery::StaticEnodeDiscovery;
 pub use self::error::{Error, ErrorKind};
-pub use self::service::Service;
+pub use self::ipc_client::IpcClient;
+pub use self::jsonrpc_client::JsonRpcClient;
+pub use self::peers::PeerRefresh;
+pub use self::registry_client::{
+    request_hmac, signed_message, unix_timestamp, RegistryClient, HMAC_HEADER, SIGNATURE_HEADER,
+    TIMESTAMP_HEADER,
+};
+pub use self::resolver::EnodeResolver;
+pub use self::service::{fetch_enode_url, Service, State};
 
-use super::types::{EthereumNodeUrl, EthereumProgram, NodeType};
+use super::metrics::Metrics;
+use super::types::{
+    BootnodeServiceParameters, ChainParameters, DiscoveryFailurePolicy, DiscoveryParameters,
+    EnodeDeregistration, EnodeInfo, EthereumNodeUrl, EthereumProgram, NodeHost, NodeType,
+    RegistryAuth, RegistryResponse,
+};
 
+/// fetch static enodes of `network_name` and resolve their host names, peers with different or,
+/// unless `accept_missing_genesis_inputs_hash`, unknown genesis are rejected
 pub fn fetch_static_enodes(
-    bootnode_service_host: &String,
-    bootnode_service_port: u16,
+    registry_client: &RegistryClient,
+    resolver: &EnodeResolver,
     network_name: &String,
+    genesis_inputs_hash: &String,
+    accept_missing_genesis_inputs_hash: bool,
 ) -> impl Future<Item = Vec<EthereumNodeUrl>, Error = Error> {
-    let client = Client::new();
-    let req = {
-        let query_uri = Uri::builder()
-            .scheme("http")
-            .authority(format!("{}:{}", bootnode_service_host, bootnode_service_port).as_str())
-            .path_and_query(format!("/staticenodes?network={}", network_name).as_str())
-            .build()
-            .unwrap();
-
-        Request::builder()
-            .uri(&query_uri)
-            .method("GET")
-            .header("Content-Type", "application/json")
-            .body(Body::empty())
-            .expect("request builder")
-    };
+    let path_and_query = format!(
+        "/staticenodes?network={}",
+        url::form_urlencoded::byte_serialize(network_name.as_bytes()).collect::<String>()
+    );
 
-    client
-        .request(req)
-        .and_then(|res| res.into_body().concat2())
-        .and_then(|data| {
+    let genesis_inputs_hash = genesis_inputs_hash.clone();
+    let resolver = resolver.clone();
+    registry_client
+        .request(Method::GET, path_and_query.as_str(), String::new())
+        .and_then(move |(status, data)| {
             use serde_json::Value as JsonValue;
-            use url::Url;
+
+            if status != StatusCode::OK {
+                return Err(Error::from(ErrorKind::RegistryRejected(
+                    status.as_u16(),
+                    String::from_utf8_lossy(&data).into_owned(),
+                )));
+            }
             match serde_json::from_slice(&data) {
-                Ok(JsonValue::Array(arr)) => Ok(arr.iter().fold(Vec::new(), |mut vec, value| {
-                    let value = match value {
-                        JsonValue::String(s) => s,
-                        _ => return vec,
-                    };
-                    let url = match value.parse::<Url>() {
-                        Ok(uri) => uri,
-                        _ => return vec,
-                    };
-
-                    if url.scheme() != "enode"
-                        || url.username().is_empty()
-                        || url.host().is_none()
-                        || url.port().is_none()
-                    {
-                        return vec;
-                    }
-
-                    let host = url.host_str().unwrap();
-                    let port = url.port().unwrap_or(30303);
-                    let addr = match host.parse::<std::net::IpAddr>() {
-                        Ok(addr) => addr,
-                        Err(_) => return vec,
-                    };
-
-                    vec.push(EthereumNodeUrl {
-                        node_id: url.username().to_owned(),
-                        port,
-                        addr,
-                    });
-                    vec
-                })),
+                Ok(JsonValue::Array(arr)) => Ok(arr
+                    .iter()
+                    .filter_map(|value| {
+                        parse_static_enode(
+                            value,
+                            &genesis_inputs_hash,
+                            accept_missing_genesis_inputs_hash,
+                        )
+                    })
+                    .collect()),
                 _ => Ok(vec![]),
             }
         })
-        .from_err()
+        .and_then(move |enode_urls| resolver.resolve(enode_urls))
+}
+
+/// static enode is either an enode URL, or an object of enode URL and genesis inputs hash
+fn parse_static_enode(
+    value: &serde_json::Value,
+    genesis_inputs_hash: &String,
+    accept_missing_genesis_inputs_hash: bool,
+) -> Option<EthereumNodeUrl> {
+    use serde_json::Value as JsonValue;
+
+    let (value, peer_genesis_inputs_hash) = match value {
+        JsonValue::String(s) => (s, None),
+        JsonValue::Object(obj) => match (obj.get("enode"), obj.get("genesisInputsHash")) {
+            (Some(JsonValue::String(s)), Some(JsonValue::String(hash))) => (s, Some(hash)),
+            (Some(JsonValue::String(s)), _) => (s, None),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let enode_url = match value.parse::<EthereumNodeUrl>() {
+        Ok(enode_url) => enode_url,
+        Err(err) => {
+            warn!(target: "bootnode", "ignore static enode: {}", err);
+            return None;
+        }
+    };
+
+    match peer_genesis_inputs_hash {
+        Some(peer_genesis_inputs_hash) if peer_genesis_inputs_hash != genesis_inputs_hash => {
+            error!(target: "bootnode",
+                   "reject peer {} with different genesis inputs hash {}, ours is {}, check chain configuration of both nodes",
+                   value, peer_genesis_inputs_hash, genesis_inputs_hash);
+            return None;
+        }
+        Some(_) => {}
+        None if accept_missing_genesis_inputs_hash => {
+            warn!(target: "bootnode", "accept peer {} without genesis inputs hash", value);
+        }
+        None => {
+            warn!(target: "bootnode",
+                  "reject peer {} without genesis inputs hash, set bootnode_service.accept_missing_genesis_inputs_hash to accept it",
+                  value);
+            return None;
+        }
+    }
+
+    Some(enode_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::launcher::genesis::genesis_inputs_hash;
+    use super::*;
+
+    const ENODE: &str = "enode://6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0@10.3.58.6:30303";
+    const GENESIS_INPUTS_HASH: &str =
+        "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3";
+
+    fn parse(
+        value: serde_json::Value,
+        accept_missing_genesis_inputs_hash: bool,
+    ) -> Option<EthereumNodeUrl> {
+        parse_static_enode(
+            &value,
+            &GENESIS_INPUTS_HASH.to_owned(),
+            accept_missing_genesis_inputs_hash,
+        )
+    }
+
+    #[test]
+    fn accept_matching_genesis_inputs_hash() {
+        let enode_url = parse(
+            json!({"enode": ENODE, "genesisInputsHash": GENESIS_INPUTS_HASH}),
+            false,
+        );
+        assert_eq!(enode_url.map(|url| url.to_string()), Some(ENODE.to_owned()));
+    }
+
+    #[test]
+    fn reject_mismatching_genesis_inputs_hash() {
+        let other_hash = "41941023680923e0fe4d74a34bdac8141f2540e3ae90623718e47d66d1ca4a2d";
+        assert_eq!(
+            parse(
+                json!({"enode": ENODE, "genesisInputsHash": other_hash}),
+                false
+            ),
+            None
+        );
+        assert_eq!(
+            parse(
+                json!({"enode": ENODE, "genesisInputsHash": other_hash}),
+                true
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn reject_peer_whose_spec_differs_in_params() {
+        let spec = json!({
+            "genesis": { "gasLimit": "0x7a1200" },
+            "params": { "accountStartNonce": "0x0", "eip1283Transition": 100 },
+            "accounts": {}
+        });
+        let mut peer_spec = spec.clone();
+        peer_spec["params"]["accountStartNonce"] = json!("0x100000");
+
+        let ours = genesis_inputs_hash(&spec, EthereumProgram::Parity);
+        let theirs = genesis_inputs_hash(&peer_spec, EthereumProgram::Parity);
+        let value = json!({"enode": ENODE, "genesisInputsHash": theirs});
+        assert_eq!(parse_static_enode(&value, &ours, true), None);
+
+        // a later fork scheduled on one node only does not split the chain at genesis
+        peer_spec["params"]["accountStartNonce"] = json!("0x0");
+        peer_spec["params"]["eip1283Transition"] = json!(200);
+        let theirs = genesis_inputs_hash(&peer_spec, EthereumProgram::Parity);
+        let value = json!({"enode": ENODE, "genesisInputsHash": theirs});
+        assert!(parse_static_enode(&value, &ours, false).is_some());
+    }
+
+    #[test]
+    fn reject_missing_genesis_inputs_hash_unless_accepted() {
+        assert_eq!(parse(json!(ENODE), false), None);
+        assert_eq!(parse(json!({ "enode": ENODE }), false), None);
+
+        let enode_url = parse(json!(ENODE), true);
+        assert_eq!(enode_url.map(|url| url.to_string()), Some(ENODE.to_owned()));
+        let enode_url = parse(json!({ "enode": ENODE }), true);
+        assert_eq!(enode_url.map(|url| url.to_string()), Some(ENODE.to_owned()));
+    }
+
+    #[test]
+    fn reject_invalid_enode() {
+        assert_eq!(
+            parse(
+                json!({"enode": "enode://@10.3.58.6", "genesisInputsHash": GENESIS_INPUTS_HASH}),
+                true
+            ),
+            None
+        );
+        assert_eq!(
+            parse(json!({ "genesisInputsHash": GENESIS_INPUTS_HASH }), true),
+            None
+        );
+        assert_eq!(parse(json!(30303), true), None);
+    }
 }
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::join_all;
+use futures::{Async, Future, Poll, Stream};
+use tokio_timer::Interval;
+
+use super::ipc_client::IpcClient;
+use super::Error;
+use super::RegistryClient;
+use super::{fetch_static_enodes, EnodeResolver, EthereumNodeUrl, EthereumProgram, Metrics};
+
+type StaticEnodesFetcher = Box<Future<Item = Vec<EthereumNodeUrl>, Error = Error> + Send>;
+type PeerAdder = Box<Future<Item = (), Error = ()> + Send>;
+
+/// re-fetch static enodes periodically and add new ones to the running Ethereum client,
+/// yields every known peer whenever new ones are found, so that they can be persisted,
+/// host names are resolved again on every refresh, so peers which moved are found too
+pub struct PeerRefresh {
+    registry_client: RegistryClient,
+    resolver: EnodeResolver,
+    network_name: String,
+    genesis_inputs_hash: String,
+    accept_missing_genesis_inputs_hash: bool,
+    ethereum_program: EthereumProgram,
+    ipc_client: Arc<IpcClient>,
+    metrics: Arc<Metrics>,
+
+    ticker: Interval,
+    fetcher: Option<StaticEnodesFetcher>,
+    adder: Option<PeerAdder>,
+
+    /// peers known to Ethereum client, the latest address of each node
+    peers: Vec<EthereumNodeUrl>,
+}
+
+impl PeerRefresh {
+    pub fn new(
+        registry_client: RegistryClient,
+        network_name: String,
+        genesis_inputs_hash: String,
+        accept_missing_genesis_inputs_hash: bool,
+        ethereum_program: EthereumProgram,
+        ipc_client: IpcClient,
+        metrics: Arc<Metrics>,
+        interval: Duration,
+        peers: Vec<EthereumNodeUrl>,
+    ) -> PeerRefresh {
+        PeerRefresh {
+            registry_client,
+            resolver: EnodeResolver::new(),
+            network_name,
+            genesis_inputs_hash,
+            accept_missing_genesis_inputs_hash,
+            ethereum_program,
+            ipc_client: Arc::new(ipc_client),
+            metrics,
+
+            ticker: Interval::new_interval(interval),
+            fetcher: None,
+            adder: None,
+
+            peers,
+        }
+    }
+
+    /// remember fetched peers, returns those which were not known
+    fn merge(&mut self, fetched: Vec<EthereumNodeUrl>) -> Vec<EthereumNodeUrl> {
+        let new_peers: Vec<_> = fetched
+            .into_iter()
+            .filter(|peer| !self.peers.contains(peer))
+            .collect();
+
+        for peer in new_peers.iter() {
+            // node may have moved to another address
+            self.peers.retain(|known| known.node_id != peer.node_id);
+            self.peers.push(peer.clone());
+        }
+        new_peers
+    }
+
+    fn add_peers(&mut self, new_peers: Vec<EthereumNodeUrl>) {
+        let adders: Vec<_> = new_peers
+            .into_iter()
+            .map(|peer| {
+                self.ipc_client
+                    .add_peer(self.ethereum_program, &peer)
+                    .then(move |result| {
+                        match result {
+                            Ok(true) => info!(target: "bootnode", "add peer {}", peer.to_string()),
+                            Ok(false) => {
+                                warn!(target: "bootnode", "Ethereum client refused peer {}",
+                                               peer.to_string())
+                            }
+                            Err(err) => warn!(target: "bootnode", "failed to add peer {}: {}",
+                                              peer.to_string(), err),
+                        }
+                        Ok(())
+                    })
+            })
+            .collect();
+
+        self.adder = Some(Box::new(join_all(adders).map(|_| ())));
+    }
+}
+
+impl Stream for PeerRefresh {
+    type Item = Vec<EthereumNodeUrl>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Error> {
+        loop {
+            let added = match self.adder {
+                Some(ref mut adder) => match adder.poll() {
+                    Ok(Async::NotReady) => false,
+                    _ => true,
+                },
+                None => false,
+            };
+            if added {
+                self.adder = None;
+            }
+
+            if self.fetcher.is_none() {
+                match self.ticker.poll()? {
+                    Async::Ready(_) => {}
+                    Async::NotReady => return Ok(Async::NotReady),
+                }
+
+                // previous peers are still being added
+                if self.adder.is_some() {
+                    continue;
+                }
+                self.fetcher = Some(Box::new(fetch_static_enodes(
+                    &self.registry_client,
+                    &self.resolver,
+                    &self.network_name,
+                    &self.genesis_inputs_hash,
+                    self.accept_missing_genesis_inputs_hash,
+                )));
+            }
+
+            let fetched = match self.fetcher {
+                Some(ref mut fetcher) => match fetcher.poll() {
+                    Ok(Async::Ready(nodes)) => Some(nodes),
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(err) => {
+                        warn!(target: "bootnode", "failed to refresh static enodes: {}", err);
+                        None
+                    }
+                },
+                None => None,
+            };
+            self.fetcher = None;
+
+            let new_peers = match fetched {
+                Some(fetched) => self.merge(fetched),
+                None => continue,
+            };
+            if new_peers.is_empty() {
+                continue;
+            }
+
+            info!(target: "bootnode", "found {} new static enodes", new_peers.len());
+            self.metrics.set_static_enodes(self.peers.len());
+            self.add_peers(new_peers);
+            return Ok(Async::Ready(Some(self.peers.clone())));
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ethkey::{KeyPair, Message};
+use futures::{future, Future, Stream};
+use hmac::{Hmac, Mac};
+use hyper::client::HttpConnector;
+use hyper::{Body, Chunk, Client, Method, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use native_tls::{Certificate, TlsConnector};
+use sha2::Sha256;
+use tiny_keccak::{Hasher, Keccak};
+
+use super::{BootnodeServiceParameters, RegistryAuth};
+use super::{Error, ErrorKind};
+
+/// hex encoded HMAC-SHA256 of `<METHOD> <path and query>\n<timestamp>\n<body>` with shared
+/// network secret
+pub const HMAC_HEADER: &str = "X-Bootnode-Hmac";
+
+/// hex encoded signature of Keccak-256 of `<timestamp>\n<body>` with node key
+pub const SIGNATURE_HEADER: &str = "X-Enode-Signature";
+
+/// seconds since UNIX epoch when request was sent, covered by HMAC and signature so that a
+/// recorded request cannot be replayed later
+pub const TIMESTAMP_HEADER: &str = "X-Enode-Timestamp";
+
+const PEM_CERTIFICATE_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_CERTIFICATE_END: &str = "-----END CERTIFICATE-----";
+
+type ResponseFuture = Box<Future<Item = (StatusCode, Chunk), Error = Error> + Send>;
+
+/// HTTP or HTTPS client of bootnode service, authenticates every request
+#[derive(Clone)]
+pub struct RegistryClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+    base_url: String,
+    auth: RegistryAuth,
+
+    /// node key to sign request bodies with
+    node_key: Option<Arc<KeyPair>>,
+}
+
+impl RegistryClient {
+    pub fn new(
+        parameters: &BootnodeServiceParameters,
+        node_key: Option<KeyPair>,
+    ) -> Result<RegistryClient, Error> {
+        let mut tls = TlsConnector::builder();
+        if let Some(ref ca_file) = parameters.ca_file {
+            let bundle = std::fs::read_to_string(ca_file)?;
+            let certificates = pem_certificates(&bundle);
+            if certificates.is_empty() {
+                return Err(Error::from(ErrorKind::InvalidCaBundle(ca_file.clone())));
+            }
+            for certificate in certificates {
+                tls.add_root_certificate(Certificate::from_pem(certificate.as_bytes())?);
+            }
+        }
+
+        let mut http = HttpConnector::new(1);
+        http.enforce_http(false);
+        let connector = HttpsConnector::from((http, tls.build()?));
+
+        let scheme = if parameters.tls { "https" } else { "http" };
+
+        Ok(RegistryClient {
+            client: Client::builder().build::<_, Body>(connector),
+            base_url: format!("{}://{}:{}", scheme, parameters.host, parameters.port),
+            auth: parameters.auth.clone(),
+            node_key: node_key.map(Arc::new),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    /// send request and collect response body, whatever its status is
+    pub fn request(&self, method: Method, path_and_query: &str, body: String) -> ResponseFuture {
+        let req = match self.build_request(method, path_and_query, body) {
+            Ok(req) => req,
+            Err(err) => return Box::new(future::err(err)),
+        };
+
+        let future = self
+            .client
+            .request(req)
+            .from_err::<Error>()
+            .and_then(|res| {
+                let status = res.status();
+                res.into_body()
+                    .concat2()
+                    .from_err::<Error>()
+                    .map(move |data| (status, data))
+            });
+        Box::new(future)
+    }
+
+    fn build_request(
+        &self,
+        method: Method,
+        path_and_query: &str,
+        body: String,
+    ) -> Result<Request<Body>, Error> {
+        let mut builder = Request::builder();
+        builder
+            .uri(format!("{}{}", self.base_url, path_and_query).as_str())
+            .method(method.clone())
+            .header("Content-Type", "application/json");
+
+        let timestamp = unix_timestamp().to_string();
+        builder.header(TIMESTAMP_HEADER, timestamp.as_str());
+
+        match self.auth {
+            RegistryAuth::None => {}
+            RegistryAuth::Bearer(ref token) => {
+                builder.header(
+                    "Authorization",
+                    format!("Bearer {}", token.as_str()).as_str(),
+                );
+            }
+            RegistryAuth::Hmac(ref secret) => {
+                let mac = request_hmac(
+                    secret.as_str(),
+                    &method,
+                    path_and_query,
+                    timestamp.as_str(),
+                    body.as_bytes(),
+                );
+                builder.header(HMAC_HEADER, mac.as_str());
+            }
+        }
+
+        if let (Some(ref node_key), false) = (&self.node_key, body.is_empty()) {
+            let message = signed_message(timestamp.as_str(), body.as_bytes());
+            let signature = ethkey::sign(node_key.secret(), &message)?;
+            builder.header(SIGNATURE_HEADER, signature.to_string().as_str());
+        }
+
+        Ok(builder.body(Body::from(body))?)
+    }
+}
+
+/// seconds since UNIX epoch, see `TIMESTAMP_HEADER`
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0)
+}
+
+/// hex encoded HMAC-SHA256 of request, see `HMAC_HEADER`
+pub fn request_hmac(
+    secret: &str,
+    method: &Method,
+    path_and_query: &str,
+    timestamp: &str,
+    body: &[u8],
+) -> String {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes())
+        .expect("HMAC accepts keys of any length; qed");
+    mac.input(format!("{} {}\n{}\n", method, path_and_query, timestamp).as_bytes());
+    mac.input(body);
+    to_hex(&mac.result().code())
+}
+
+/// Keccak-256 of request timestamp and body, which is signed with node key
+pub fn signed_message(timestamp: &str, body: &[u8]) -> Message {
+    let mut hasher = Keccak::v256();
+    hasher.update(format!("{}\n", timestamp).as_bytes());
+    hasher.update(body);
+
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    Message::from(hash)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// every certificate of PEM bundle, `Certificate::from_pem` reads the first one only
+fn pem_certificates(bundle: &str) -> Vec<&str> {
+    let mut certificates = Vec::new();
+    let mut rest = bundle;
+    while let Some(begin) = rest.find(PEM_CERTIFICATE_BEGIN) {
+        let end = match rest[begin..].find(PEM_CERTIFICATE_END) {
+            Some(end) => begin + end + PEM_CERTIFICATE_END.len(),
+            None => break,
+        };
+        certificates.push(&rest[begin..end]);
+        rest = &rest[end..];
+    }
+    certificates
+}
@@ -0,0 +1,78 @@
+use futures::future::{self, join_all};
+use futures::Future;
+use hyper::client::connect::dns::{GaiResolver, Name, Resolve};
+
+use super::{Error, EthereumNodeUrl, NodeHost};
+
+type ResolveFuture = Box<Future<Item = Vec<EthereumNodeUrl>, Error = Error> + Send>;
+type ResolveOneFuture = Box<Future<Item = Option<EthereumNodeUrl>, Error = Error> + Send>;
+
+/// resolves host names of enode URLs on a background thread, as Ethereum clients only accept
+/// IP addresses of peers
+#[derive(Clone)]
+pub struct EnodeResolver {
+    resolver: GaiResolver,
+}
+
+impl EnodeResolver {
+    pub fn new() -> EnodeResolver {
+        EnodeResolver {
+            resolver: GaiResolver::new(1),
+        }
+    }
+
+    /// resolve host names to their first address, preferably IPv4,
+    /// enode URLs whose host names do not resolve are dropped
+    pub fn resolve(&self, enode_urls: Vec<EthereumNodeUrl>) -> ResolveFuture {
+        let resolvers: Vec<_> = enode_urls
+            .into_iter()
+            .map(|enode_url| self.resolve_one(enode_url))
+            .collect();
+
+        Box::new(
+            join_all(resolvers)
+                .map(|enode_urls| enode_urls.into_iter().filter_map(|url| url).collect()),
+        )
+    }
+
+    fn resolve_one(&self, enode_url: EthereumNodeUrl) -> ResolveOneFuture {
+        let name = match enode_url.host {
+            NodeHost::Ip(_) => return Box::new(future::ok(Some(enode_url))),
+            NodeHost::Name(ref name) => name.parse::<Name>(),
+        };
+        let name = match name {
+            Ok(name) => name,
+            Err(_) => {
+                warn!(target: "bootnode", "invalid host name of {}", enode_url.to_string());
+                return Box::new(future::ok(None));
+            }
+        };
+
+        let future = self.resolver.resolve(name).then(move |result| {
+            let addr = result.map(|addrs| {
+                let addrs: Vec<_> = addrs.collect();
+                addrs
+                    .iter()
+                    .find(|addr| addr.is_ipv4())
+                    .or_else(|| addrs.first())
+                    .cloned()
+            });
+
+            match addr {
+                Ok(Some(addr)) => {
+                    debug!(target: "bootnode", "resolved {} to {}", enode_url.to_string(), addr);
+                    Ok(Some(enode_url.with_addr(addr)))
+                }
+                Ok(None) => {
+                    warn!(target: "bootnode", "no address of {}", enode_url.to_string());
+                    Ok(None)
+                }
+                Err(err) => {
+                    warn!(target: "bootnode", "failed to resolve {}: {}", enode_url.to_string(), err);
+                    Ok(None)
+                }
+            }
+        });
+        Box::new(future)
+    }
+}
@@ -1,17 +1,35 @@
 use futures::{sync::mpsc, Async, Future, Poll, Stream};
-use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use hyper::{Body, Client, Request, Uri};
+use hyper::{Method, StatusCode};
+use tokio_timer::Timeout;
 
+use super::ipc_client::IpcClient;
 use super::jsonrpc_client::JsonRpcClient;
+use super::registry_client::RegistryClient;
+use super::Metrics;
+use super::{ChainParameters, EnodeDeregistration, EnodeInfo, RegistryResponse};
 use super::{Error, ErrorKind};
-use super::{EthereumNodeUrl, EthereumProgram, NodeType};
+use super::{EthereumNodeUrl, EthereumProgram, NodeHost, NodeType};
 
-#[derive(Copy, Clone)]
-enum State {
+#[derive(Copy, Clone, PartialEq)]
+pub enum State {
     Idle,
     FetchingEthereumNodeUrl,
     UpdatingEthereumNodeUrl,
+    DeregisteringEthereumNodeUrl,
+}
+
+impl State {
+    pub fn all() -> Vec<State> {
+        vec![
+            State::Idle,
+            State::FetchingEthereumNodeUrl,
+            State::UpdatingEthereumNodeUrl,
+            State::DeregisteringEthereumNodeUrl,
+        ]
+    }
 }
 
 impl ToString for State {
@@ -20,124 +38,263 @@ impl ToString for State {
             State::Idle => "Idle".to_owned(),
             State::FetchingEthereumNodeUrl => "FetchingEnodeUrl".to_owned(),
             State::UpdatingEthereumNodeUrl => "UpdatingEnodeUrl".to_owned(),
+            State::DeregisteringEthereumNodeUrl => "DeregisteringEnodeUrl".to_owned(),
         }
     }
 }
 
-type UrlFetcher = Box<Future<Item = EthereumNodeUrl, Error = Error> + Send>;
-type UrlUpdater = Box<Future<Item = bool, Error = Error> + Send>;
+/// bootnode service must respond to deregistration within this time, so that it does not
+/// delay shutdown
+const DEREGISTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// bootnode service must respond to registration within this time, so that a stalled service
+/// does not block later updates
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type UrlFetcher = Box<Future<Item = EthereumNodeUrl, Error = Error> + Send>;
+type RegistryRequest = Box<Future<Item = RegistryResponse, Error = Error> + Send>;
 
 enum StateWorker {
     Idle,
     Fetcher { fetcher: UrlFetcher },
-    Updater { updater: UrlUpdater },
+    Updater { updater: RegistryRequest },
+    Deregisterer { deregisterer: RegistryRequest },
+}
+
+/// query enode URL of running Ethereum client, Parity over JSON RPC and go-ethereum over IPC,
+/// `fallback_enode_url` is used if the client does not expose its enode URL
+pub fn fetch_enode_url(
+    jsonrpc_client: &JsonRpcClient,
+    ipc_client: &IpcClient,
+    ethereum_program: EthereumProgram,
+    fallback_enode_url: Option<EthereumNodeUrl>,
+) -> UrlFetcher {
+    let fetcher: UrlFetcher = match ethereum_program {
+        EthereumProgram::Parity => Box::new(jsonrpc_client.parity_enode()),
+        EthereumProgram::GoEthereum => Box::new(ipc_client.admin_node_info()),
+    };
+
+    // the client is running but does not expose its enode URL
+    match fallback_enode_url {
+        Some(enode_url) => Box::new(fetcher.or_else(move |err| match err {
+            Error(ErrorKind::JsonRpc(_), _)
+            | Error(ErrorKind::UnexpectedJsonRpcResult(_, _), _) => {
+                warn!(target: "bootnode", "Failed to fetch enode URL: {}, derive it from node key", err);
+                Ok(enode_url)
+            }
+            err => Err(err),
+        })),
+        None => fetcher,
+    }
 }
 
 impl StateWorker {
-    fn new_fetcher(client: &JsonRpcClient, _ethereum_program: EthereumProgram) -> StateWorker {
+    fn new_fetcher(
+        jsonrpc_client: &JsonRpcClient,
+        ipc_client: &IpcClient,
+        ethereum_program: EthereumProgram,
+        fallback_enode_url: Option<EthereumNodeUrl>,
+    ) -> StateWorker {
         StateWorker::Fetcher {
-            fetcher: Box::new(client.parity_enode().from_err()),
+            fetcher: fetch_enode_url(
+                jsonrpc_client,
+                ipc_client,
+                ethereum_program,
+                fallback_enode_url,
+            ),
         }
     }
 
     fn new_updater(
-        bootstrap_service_url: &Uri,
-        public_ip: &IpAddr,
+        registry_client: &RegistryClient,
+        public_host: &NodeHost,
         enode_url: EthereumNodeUrl,
         network_name: String,
+        chain: &ChainParameters,
+        genesis_inputs_hash: String,
         is_miner: bool,
+        lease: Duration,
     ) -> StateWorker {
-        #[derive(Clone, Serialize)]
-        struct EnodeInfo {
-            enode: String,
-            port: u16,
-            ip: String,
-            #[serde(rename = "publicIp")]
-            public_ip: String,
-            network: String,
-            miner: bool,
-        }
-
-        let req = Request::builder()
-            .uri(bootstrap_service_url)
-            .method("POST")
-            .header("Content-Type", "application/json")
-            .body(Body::from({
-                let enode_info = EnodeInfo {
-                    enode: enode_url.node_id,
-                    port: enode_url.port,
-                    ip: enode_url.addr.to_string(),
-                    public_ip: public_ip.to_string(),
-                    network: network_name,
-                    miner: is_miner,
-                };
-                let info =
-                    serde_json::to_string(&enode_info).expect("EnodeInfo is serializable; qed");
-
-                info!(target: "bootnode", "Update ethereum node info {}", info);
-
-                info
-            }))
-            .expect("request builder");
-
-        let future = Client::new()
-            .request(req)
-            .and_then(|res| res.into_body().concat2())
-            .from_err::<Error>()
-            .and_then(|_data| Ok(true))
-            .from_err::<Error>();
+        let enode_info = EnodeInfo {
+            enode: enode_url.node_id,
+            port: enode_url.port,
+            ip: enode_url.host.to_string(),
+            public_ip: public_host.to_string(),
+            discovery_port: enode_url.discovery_port,
+            network: network_name,
+            network_id: chain.network_id,
+            chain_id: chain.chain_id,
+            genesis_inputs_hash: Some(genesis_inputs_hash),
+            miner: is_miner,
+            ttl: Some(lease.as_secs()),
+        };
+        let info = serde_json::to_string(&enode_info).expect("EnodeInfo is serializable; qed");
+
+        info!(target: "bootnode", "Update ethereum node info {}", info);
 
+        let request = registry_request(registry_client, Method::POST, info);
         StateWorker::Updater {
-            updater: Box::new(future),
+            updater: with_timeout(request, UPDATE_TIMEOUT),
+        }
+    }
+
+    fn new_deregisterer(
+        registry_client: &RegistryClient,
+        node_id: String,
+        network_name: String,
+    ) -> StateWorker {
+        let deregistration = EnodeDeregistration {
+            enode: node_id,
+            network: network_name,
+        };
+        let body = serde_json::to_string(&deregistration)
+            .expect("EnodeDeregistration is serializable; qed");
+
+        info!(target: "bootnode", "Deregister ethereum node {}", body);
+
+        let request = registry_request(registry_client, Method::DELETE, body);
+        StateWorker::Deregisterer {
+            deregisterer: with_timeout(request, DEREGISTER_TIMEOUT),
+        }
+    }
+}
+
+/// send request to bootnode service, it fails unless bootnode service accepts it
+fn registry_request(client: &RegistryClient, method: Method, body: String) -> RegistryRequest {
+    let future = client
+        .request(method, "/", body)
+        .and_then(|(status, data)| parse_registry_response(status, &data));
+
+    Box::new(future)
+}
+
+/// fail request if bootnode service does not respond within `timeout`
+fn with_timeout(request: RegistryRequest, timeout: Duration) -> RegistryRequest {
+    let future = Timeout::new(request, timeout).map_err(move |err| {
+        if err.is_elapsed() {
+            return Error::from(ErrorKind::RegistryTimeout(timeout));
+        }
+        match err.into_inner() {
+            Some(err) => err,
+            None => Error::from(ErrorKind::RegistryTimeout(timeout)),
+        }
+    });
+
+    Box::new(future)
+}
+
+fn parse_registry_response(status: StatusCode, data: &[u8]) -> Result<RegistryResponse, Error> {
+    let response = if data.iter().all(u8::is_ascii_whitespace) {
+        // bootnode services without response body accept requests by status only
+        RegistryResponse {
+            ok: status.is_success(),
+            ..Default::default()
+        }
+    } else {
+        match serde_json::from_slice::<RegistryResponse>(data) {
+            Ok(response) => response,
+            Err(_) if !status.is_success() => RegistryResponse {
+                ok: false,
+                error: Some(String::from_utf8_lossy(data).into_owned()),
+                ttl: None,
+            },
+            Err(err) => return Err(Error::from(err)),
         }
+    };
+
+    if !status.is_success() || !response.ok {
+        let reason = response.error.unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("request rejected")
+                .to_owned()
+        });
+        return Err(Error::from(ErrorKind::RegistryRejected(
+            status.as_u16(),
+            reason,
+        )));
     }
+
+    Ok(response)
 }
 
 pub struct Service {
     network_name: String,
+    chain: ChainParameters,
+    /// hash of rendered chain spec, peers only sync with the same genesis
+    genesis_inputs_hash: String,
     ethereum_program: EthereumProgram,
     node_type: NodeType,
-    bootnode_service_uri: Uri,
-    public_ip: IpAddr,
+    registry_client: RegistryClient,
+    /// public host name or public IP
+    public_host: NodeHost,
+    /// P2P port reachable on public host
+    external_network_port: u16,
     jsonrpc_client: JsonRpcClient,
+    ipc_client: IpcClient,
+    /// enode URL derived from node key, P2P port and public IP, registered if the client
+    /// does not expose its own
+    fallback_enode_url: Option<EthereumNodeUrl>,
     state: State,
     state_worker: StateWorker,
 
+    /// registration expires if it is not renewed within lease
+    lease: Duration,
+    /// node ID of latest registration, deregistered on stop
+    node_id: Option<String>,
+
+    metrics: Arc<Metrics>,
+    /// start time of current update
+    update_started_at: Option<Instant>,
+
     event_sender: mpsc::UnboundedSender<()>,
     event_receiver: mpsc::UnboundedReceiver<()>,
+    /// set on stop, events still buffered in receiver are ignored
+    stopping: bool,
 }
 
 impl Service {
     pub fn new(
         network_name: String,
+        chain: ChainParameters,
+        genesis_inputs_hash: String,
         ethereum_program: EthereumProgram,
         node_type: NodeType,
-        bootnode_service_host: String,
-        bootnode_service_port: u16,
-        public_ip: IpAddr,
+        registry_client: RegistryClient,
+        public_host: NodeHost,
+        external_network_port: u16,
         ethereum_node_endpoint: String,
+        ipc_client: IpcClient,
+        fallback_enode_url: Option<EthereumNodeUrl>,
+        lease: Duration,
+        metrics: Arc<Metrics>,
     ) -> Service {
-        let bootnode_service_uri = format!(
-            "http://{}:{}/",
-            bootnode_service_host, bootnode_service_port
-        )
-        .parse()
-        .expect("bootnode URI");
-
         let (event_sender, event_receiver) = mpsc::unbounded();
 
         Service {
             network_name,
+            chain,
+            genesis_inputs_hash,
             ethereum_program,
-            bootnode_service_uri,
+            registry_client,
             jsonrpc_client: JsonRpcClient::new(&ethereum_node_endpoint),
+            ipc_client,
+            fallback_enode_url,
             node_type,
             state: State::Idle,
             state_worker: StateWorker::Idle,
-            public_ip,
+            public_host,
+            external_network_port,
+
+            lease,
+            node_id: None,
+
+            metrics,
+            update_started_at: None,
 
             event_sender,
             event_receiver,
+            stopping: false,
         }
     }
 
@@ -147,16 +304,52 @@ impl Service {
             .expect("receiver always existed; qed");
     }
 
+    /// drop in-flight update, stop accepting update events and deregister this node,
+    /// the stream ends when deregistration is finished
+    pub fn stop(&mut self) {
+        self.reset();
+        self.stopping = true;
+        self.event_receiver.close();
+
+        if let Some(node_id) = self.node_id.take() {
+            self.set_state(State::DeregisteringEthereumNodeUrl);
+            self.state_worker = StateWorker::new_deregisterer(
+                &self.registry_client,
+                node_id,
+                self.network_name.clone(),
+            );
+        }
+    }
+
     fn reset(&mut self) {
-        self.state = State::Idle;
+        self.set_state(State::Idle);
         self.state_worker = StateWorker::Idle;
+        self.update_started_at = None;
+    }
+
+    fn set_state(&mut self, state: State) {
+        self.state = state;
+        self.metrics.set_bootnode_state(state);
+    }
+
+    /// record result and duration of current update
+    fn update_finished(&mut self, success: bool) {
+        if let Some(started_at) = self.update_started_at.take() {
+            self.metrics.bootnode_updated(success, started_at.elapsed());
+        }
     }
 
     fn poll_idle(&mut self) -> Poll<Option<()>, Error> {
+        // update events buffered before stop must not register this node again
+        if self.stopping {
+            return Ok(Async::Ready(None));
+        }
+
         if let StateWorker::Idle = self.state_worker {
             match self.event_receiver.poll().unwrap() {
                 Async::Ready(Some(_)) => {}
-                _ => return Ok(Async::NotReady),
+                Async::Ready(None) => return Ok(Async::Ready(None)),
+                Async::NotReady => return Ok(Async::NotReady),
             }
         } else {
             return Err(Error::from(ErrorKind::InvalidStateTransfer(
@@ -166,12 +359,30 @@ impl Service {
         }
 
         // transfer state
-        self.state = State::FetchingEthereumNodeUrl;
-        self.state_worker = StateWorker::new_fetcher(&self.jsonrpc_client, self.ethereum_program);
+        self.set_state(State::FetchingEthereumNodeUrl);
+        self.update_started_at = Some(Instant::now());
+        self.state_worker = StateWorker::new_fetcher(
+            &self.jsonrpc_client,
+            &self.ipc_client,
+            self.ethereum_program,
+            self.fallback_enode_url.clone(),
+        );
 
         Ok(Async::NotReady)
     }
 
+    /// the client reports its listen address, which is private inside a container
+    fn advertised_enode_url(&self, enode_url: EthereumNodeUrl) -> EthereumNodeUrl {
+        if self.public_host.is_unspecified() {
+            return enode_url;
+        }
+        EthereumNodeUrl {
+            host: self.public_host.clone(),
+            port: self.external_network_port,
+            ..enode_url
+        }
+    }
+
     fn poll_fetching(&mut self) -> Poll<Option<()>, Error> {
         let enode_url = if let StateWorker::Fetcher { ref mut fetcher } = self.state_worker {
             match fetcher.poll() {
@@ -185,30 +396,35 @@ impl Service {
                 State::FetchingEthereumNodeUrl.to_string(),
             )));
         };
+        let enode_url = self.advertised_enode_url(enode_url);
 
         info!(
             "Update enode url {:?} to {}",
             enode_url.to_string(),
-            self.bootnode_service_uri
+            self.registry_client.base_url()
         );
 
         // transfer state
-        self.state = State::UpdatingEthereumNodeUrl;
+        self.node_id = Some(enode_url.node_id.clone());
+        self.set_state(State::UpdatingEthereumNodeUrl);
         self.state_worker = StateWorker::new_updater(
-            &self.bootnode_service_uri,
-            &self.public_ip,
+            &self.registry_client,
+            &self.public_host,
             enode_url,
             self.network_name.clone(),
+            &self.chain,
+            self.genesis_inputs_hash.clone(),
             self.node_type.is_miner(),
+            self.lease,
         );
 
         Ok(Async::NotReady)
     }
 
     fn poll_updating(&mut self) -> Poll<Option<()>, Error> {
-        let ok = if let StateWorker::Updater { ref mut updater } = self.state_worker {
+        let response = if let StateWorker::Updater { ref mut updater } = self.state_worker {
             match updater.poll() {
-                Ok(Async::Ready(result)) => result,
+                Ok(Async::Ready(response)) => response,
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Err(err) => return Err(Error::from(err)),
             }
@@ -219,12 +435,43 @@ impl Service {
             )));
         };
 
-        if !ok {
-            warn!(target: "bootnode", "Failed to update enode URL");
+        if let Some(ttl) = response.ttl {
+            if ttl < self.lease.as_secs() {
+                warn!(target: "bootnode", "Bootnode service granted lease of {}s, requested {:?}",
+                      ttl, self.lease);
+            }
         }
+        self.update_finished(true);
 
         // transfer state
-        self.state = State::Idle;
+        self.set_state(State::Idle);
+        self.state_worker = StateWorker::Idle;
+
+        Ok(Async::NotReady)
+    }
+
+    fn poll_deregistering(&mut self) -> Poll<Option<()>, Error> {
+        if let StateWorker::Deregisterer {
+            ref mut deregisterer,
+        } = self.state_worker
+        {
+            match deregisterer.poll() {
+                Ok(Async::Ready(_)) => {}
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => return Err(Error::from(err)),
+            }
+        } else {
+            return Err(Error::from(ErrorKind::InvalidStateTransfer(
+                self.state.to_string(),
+                State::DeregisteringEthereumNodeUrl.to_string(),
+            )));
+        }
+
+        info!(target: "bootnode", "Deregistered enode URL from {}",
+              self.registry_client.base_url());
+
+        // transfer state
+        self.set_state(State::Idle);
         self.state_worker = StateWorker::Idle;
 
         Ok(Async::NotReady)
@@ -237,18 +484,88 @@ impl Stream for Service {
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Error> {
         loop {
-            let result = match self.state {
+            let state = self.state;
+            let result = match state {
                 State::Idle => self.poll_idle(),
                 State::FetchingEthereumNodeUrl => self.poll_fetching(),
                 State::UpdatingEthereumNodeUrl => self.poll_updating(),
+                State::DeregisteringEthereumNodeUrl => self.poll_deregistering(),
             };
 
-            if result.is_err() {
-                self.reset();
+            match result {
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(None)),
+                Err(ref err) if state == State::DeregisteringEthereumNodeUrl => {
+                    warn!(target: "bootnode", "Failed to deregister enode URL: {}", err);
+                    self.reset();
+                }
+                Err(err) => {
+                    warn!(target: "bootnode", "Failed to update enode URL: {}", err);
+                    self.update_finished(false);
+                    self.reset();
+                }
+                Ok(_) => {}
+            }
+
+            // poll the worker of new state, so that it notifies this task
+            if self.state == state {
                 return Ok(Async::NotReady);
             }
+        }
+    }
+}
 
-            return Ok(Async::NotReady);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejection(status: StatusCode, data: &[u8]) -> Option<(u16, String)> {
+        match parse_registry_response(status, data) {
+            Err(Error(ErrorKind::RegistryRejected(code, reason), _)) => Some((code, reason)),
+            _ => None,
         }
     }
+
+    #[test]
+    fn accept_response_with_ttl() {
+        let response =
+            parse_registry_response(StatusCode::OK, br#"{"ok":true,"ttl":120}"#).unwrap();
+        assert_eq!(response.ttl, Some(120));
+    }
+
+    #[test]
+    fn accept_empty_successful_response() {
+        let response = parse_registry_response(StatusCode::OK, b"").unwrap();
+        assert!(response.ok);
+        assert_eq!(response.ttl, None);
+        assert!(parse_registry_response(StatusCode::NO_CONTENT, b" \n").is_ok());
+    }
+
+    #[test]
+    fn reject_by_body_or_status() {
+        assert_eq!(
+            rejection(StatusCode::OK, br#"{"ok":false,"error":"unknown network"}"#),
+            Some((200, "unknown network".to_owned()))
+        );
+        assert_eq!(
+            rejection(
+                StatusCode::BAD_REQUEST,
+                br#"{"ok":false,"error":"ttl is 0"}"#
+            ),
+            Some((400, "ttl is 0".to_owned()))
+        );
+        assert_eq!(
+            rejection(StatusCode::BAD_GATEWAY, b"upstream is down"),
+            Some((502, "upstream is down".to_owned()))
+        );
+        assert_eq!(
+            rejection(StatusCode::SERVICE_UNAVAILABLE, b""),
+            Some((503, "Service Unavailable".to_owned()))
+        );
+    }
+
+    #[test]
+    fn malformed_successful_response_is_an_error() {
+        assert!(parse_registry_response(StatusCode::OK, b"<html></html>").is_err());
+        assert_eq!(rejection(StatusCode::OK, b"<html></html>"), None);
+    }
 }
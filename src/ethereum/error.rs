@@ -1,5 +1,14 @@
 error_chain! {
     foreign_links {
         StdIoError(std::io::Error);
+        TimerError(tokio_timer::Error);
+        LauncherError(super::LauncherError);
+    }
+
+    errors {
+        CrashLoop(restarts: usize, window: std::time::Duration) {
+            description("Ethereum client is crash looping")
+            display("Ethereum client is crash looping: {} restarts within {:?}", restarts, window)
+        }
     }
 }
@@ -1,7 +1,12 @@
-use super::launcher::EthereumLauncher;
-use super::types::RestartPolicy;
+use super::launcher::{Error as LauncherError, EthereumLauncher};
+use super::metrics::Metrics;
+use super::types::{
+    ClientLogParameters, EthereumNodeUrl, EthereumProgram, RestartParameters, RestartPolicy,
+};
 
 mod error;
+mod log_file;
+mod output;
 mod service;
 
 pub use self::error::{Error, ErrorKind};
@@ -0,0 +1,132 @@
+use futures::{Async, Poll, Stream};
+use std::io;
+use tokio::io::AsyncRead;
+use tokio_process::Child as ChildProcess;
+
+use super::log_file::RotatingFile;
+use super::EthereumProgram;
+
+const READ_CHUNK_SIZE: usize = 4096;
+
+pub type ClientOutput = Box<Stream<Item = String, Error = io::Error> + Send>;
+
+/// lines of piped stdout and stderr of Ethereum client
+pub fn client_output(process: &mut ChildProcess) -> ClientOutput {
+    let stdout = process.stdout().take().expect("stdout is piped; qed");
+    let stderr = process.stderr().take().expect("stderr is piped; qed");
+    Box::new(OutputLines::new(stdout).select(OutputLines::new(stderr)))
+}
+
+/// re-emit Ethereum client output through `log`, tagged with program and node identity,
+/// the program name is also used as log target, e.g. `RUST_LOG=parity=warn`
+pub struct ClientLogger {
+    target: &'static str,
+    identity: String,
+    file: Option<RotatingFile>,
+}
+
+impl ClientLogger {
+    pub fn new(
+        program: EthereumProgram,
+        identity: String,
+        file: Option<RotatingFile>,
+    ) -> ClientLogger {
+        ClientLogger {
+            target: match program {
+                EthereumProgram::Parity => "parity",
+                EthereumProgram::GoEthereum => "geth",
+            },
+            identity,
+            file,
+        }
+    }
+
+    pub fn log(&mut self, line: &str) {
+        let level = client_log_level(line, log::Level::Info);
+        log!(target: self.target, level, "[{} {}] {}", self.target, self.identity, line);
+
+        let failed = match self.file {
+            Some(ref mut file) => file.write_line(line).err(),
+            None => None,
+        };
+        if let Some(err) = failed {
+            warn!(target: "ethereum", "failed to write client log file, stop writing: {}", err);
+            self.file = None;
+        }
+    }
+}
+
+/// lines of Ethereum client stdout or stderr, invalid UTF-8 is replaced
+pub struct OutputLines<R> {
+    reader: Option<R>,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead> OutputLines<R> {
+    pub fn new(reader: R) -> OutputLines<R> {
+        OutputLines {
+            reader: Some(reader),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<R: AsyncRead> Stream for OutputLines<R> {
+    type Item = String;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<String>, io::Error> {
+        loop {
+            if let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                return Ok(Async::Ready(Some(to_line(&line))));
+            }
+
+            let n = match self.reader {
+                Some(ref mut reader) => {
+                    let mut chunk = [0u8; READ_CHUNK_SIZE];
+                    let n = match reader.poll_read(&mut chunk)? {
+                        Async::Ready(n) => n,
+                        Async::NotReady => return Ok(Async::NotReady),
+                    };
+                    self.buffer.extend_from_slice(&chunk[..n]);
+                    n
+                }
+                None => return Ok(Async::Ready(None)),
+            };
+
+            // EOF, flush the last line without line break
+            if n == 0 {
+                self.reader = None;
+                if !self.buffer.is_empty() {
+                    let line: Vec<u8> = self.buffer.drain(..).collect();
+                    return Ok(Async::Ready(Some(to_line(&line))));
+                }
+            }
+        }
+    }
+}
+
+fn to_line(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches(|c| c == '\r' || c == '\n')
+        .to_owned()
+}
+
+/// level of a Parity or go-ethereum log line, `default` if it has no level
+pub fn client_log_level(line: &str, default: log::Level) -> log::Level {
+    // level is one of the leading words, e.g. `... IO Worker #0 INFO import ...` or `WARN [01-02|...]`
+    line.split(|c: char| c.is_whitespace() || c == '[')
+        .filter(|word| !word.is_empty())
+        .take(8)
+        .filter_map(|word| match word {
+            "CRIT" | "ERROR" | "EROR" => Some(log::Level::Error),
+            "WARN" => Some(log::Level::Warn),
+            "INFO" => Some(log::Level::Info),
+            "DEBUG" | "DBUG" => Some(log::Level::Debug),
+            "TRACE" | "TRCE" => Some(log::Level::Trace),
+            _ => None,
+        })
+        .next()
+        .unwrap_or(default)
+}
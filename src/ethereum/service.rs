@@ -1,71 +1,318 @@
-use futures::{Async, Future, Poll, Stream};
+use std::collections::VecDeque;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use futures::{Async, Future, Poll, Stream};
+use rand::Rng;
 use tokio_process::Child as ChildProcess;
+use tokio_timer::Delay;
+
+use super::log_file::RotatingFile;
+use super::output::{client_output, ClientLogger, ClientOutput};
+use super::{ClientLogParameters, Error, ErrorKind, Metrics};
+use super::{EthereumLauncher, EthereumNodeUrl, RestartParameters, RestartPolicy};
 
-use super::Error;
-use super::{EthereumLauncher, RestartPolicy};
+/// time to wait for remaining output of exited Ethereum client
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
 
+/// Ethereum client process, yields success of every exit,
+/// ends when the client should not be restarted
 pub struct Service {
-    restart_policy: RestartPolicy,
+    restart: RestartParameters,
     ethereum_launcher: EthereumLauncher,
     ethereum_process: Option<ChildProcess>,
+    started_at: Instant,
+
+    /// whether Ethereum client process is alive, shared with health server
+    running: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+
+    /// stdout and stderr of current Ethereum client process
+    output: Option<ClientOutput>,
+    logger: ClientLogger,
+
+    /// exit status of Ethereum client, reported after its output is drained
+    exit_status: Option<ExitStatus>,
+    drain_delay: Option<Delay>,
+
+    /// pending restart
+    restart_delay: Option<Delay>,
+
+    /// consecutive restarts, for exponential backoff
+    backoff_exponent: u32,
+
+    /// time of restarts within restart window
+    restart_history: VecDeque<Instant>,
+
+    /// shutting down, Ethereum client is not restarted anymore
+    stopping: bool,
+
+    /// SIGKILL Ethereum client when grace period is over
+    kill_delay: Option<Delay>,
 }
 
 impl Service {
-    pub fn new(ethereum_launcher: EthereumLauncher, restart_policy: RestartPolicy) -> Service {
-        let ethereum_process = Some(
-            ethereum_launcher
-                .execute_async()
-                .expect("spawn Ethereum client process"),
+    pub fn new(
+        ethereum_launcher: EthereumLauncher,
+        restart: RestartParameters,
+        client_log: ClientLogParameters,
+        metrics: Arc<Metrics>,
+    ) -> Service {
+        let mut ethereum_process = ethereum_launcher
+            .execute_async()
+            .expect("spawn Ethereum client process");
+        let output = Some(client_output(&mut ethereum_process));
+
+        let log_file = if client_log.file {
+            let path = ethereum_launcher.client_log_file_path();
+            match RotatingFile::open(path.clone(), client_log.max_size, client_log.max_files) {
+                Ok(file) => Some(file),
+                Err(err) => {
+                    warn!(target: "ethereum", "failed to open client log file {:?}: {}", path, err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let logger = ClientLogger::new(
+            ethereum_launcher.engine.program(),
+            ethereum_launcher.identity(),
+            log_file,
         );
 
         Service {
-            restart_policy,
+            restart,
             ethereum_launcher,
-            ethereum_process,
+            ethereum_process: Some(ethereum_process),
+            started_at: Instant::now(),
+            running: Arc::new(AtomicBool::new(true)),
+            metrics,
+            output,
+            logger,
+            exit_status: None,
+            drain_delay: None,
+            restart_delay: None,
+            backoff_exponent: 0,
+            restart_history: VecDeque::new(),
+            stopping: false,
+            kill_delay: None,
         }
     }
 
-    pub fn restart(&mut self) {
-        std::mem::replace(
-            &mut self.ethereum_process,
-            Some(
-                self.ethereum_launcher
-                    .execute_async()
-                    .expect("spawn Ethereum client process"),
-            ),
-        );
+    pub fn restart(&mut self) -> Result<(), Error> {
+        let mut ethereum_process = self.ethereum_launcher.execute_async()?;
+        self.output = Some(client_output(&mut ethereum_process));
+        self.ethereum_process = Some(ethereum_process);
+        self.started_at = Instant::now();
+        self.running.store(true, Ordering::SeqCst);
+        self.metrics.client_restarted();
+        Ok(())
+    }
+
+    /// flag of whether Ethereum client process is alive
+    pub fn running_handle(&self) -> Arc<AtomicBool> {
+        self.running.clone()
     }
 
-    #[allow(dead_code)]
-    pub fn stop(&mut self) {
-        std::mem::replace(&mut self.ethereum_process, None);
+    /// log every available line of client output
+    fn poll_output(&mut self) {
+        let finished = match self.output {
+            Some(ref mut output) => loop {
+                match output.poll() {
+                    Ok(Async::Ready(Some(line))) => self.logger.log(line.as_str()),
+                    Ok(Async::Ready(None)) => break true,
+                    Ok(Async::NotReady) => break false,
+                    Err(err) => {
+                        warn!(target: "ethereum", "failed to read client output: {}", err);
+                        break true;
+                    }
+                }
+            },
+            None => false,
+        };
+
+        if finished {
+            self.output = None;
+        }
+    }
+
+    /// persist peers, so that they are known to Ethereum client after restart
+    pub fn update_peers(&mut self, peers: Vec<EthereumNodeUrl>) -> Result<(), Error> {
+        self.ethereum_launcher.bootnodes = peers;
+        self.ethereum_launcher.write_peers()?;
+        Ok(())
+    }
+
+    /// send SIGTERM to Ethereum client and SIGKILL it if it is still running after `grace_period`
+    pub fn stop(&mut self, grace_period: Duration) {
+        self.stopping = true;
+        self.restart_delay = None;
+
+        if let Some(ref process) = self.ethereum_process {
+            let pid = process.id();
+            info!(target: "ethereum", "send SIGTERM to Ethereum client {}, wait {:?} for it to exit",
+                  pid, grace_period);
+
+            if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+                warn!(target: "ethereum", "failed to send SIGTERM to Ethereum client {}: {}",
+                      pid, std::io::Error::last_os_error());
+            }
+            self.kill_delay = Some(Delay::new(Instant::now() + grace_period));
+        }
+    }
+
+    /// SIGKILL Ethereum client
+    pub fn kill(&mut self) {
+        self.kill_delay = None;
+        if let Some(ref mut process) = self.ethereum_process {
+            warn!(target: "ethereum", "send SIGKILL to Ethereum client {}", process.id());
+            if let Err(err) = process.kill() {
+                warn!(target: "ethereum", "failed to kill Ethereum client: {}", err);
+            }
+        }
+    }
+
+    fn should_restart(&self, success: bool) -> bool {
+        match self.restart.policy {
+            RestartPolicy::No => false,
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => !success,
+        }
+    }
+
+    /// exponential backoff with jitter, fails if there are too many restarts within restart window
+    fn schedule_restart(&mut self) -> Result<Duration, Error> {
+        let now = Instant::now();
+
+        // client has run long enough, start over again
+        if now.duration_since(self.started_at) >= self.restart.window {
+            self.backoff_exponent = 0;
+        }
+
+        while let Some(&restarted_at) = self.restart_history.front() {
+            if now.duration_since(restarted_at) < self.restart.window {
+                break;
+            }
+            self.restart_history.pop_front();
+        }
+        if self.restart_history.len() >= self.restart.max_restarts {
+            return Err(Error::from(ErrorKind::CrashLoop(
+                self.restart_history.len(),
+                self.restart.window,
+            )));
+        }
+        self.restart_history.push_back(now);
+
+        let backoff = {
+            let initial = duration_as_millis(self.restart.initial_backoff);
+            let max = duration_as_millis(self.restart.max_backoff);
+            let factor = 1u64
+                .checked_shl(self.backoff_exponent)
+                .unwrap_or(u64::max_value());
+            let backoff = std::cmp::min(initial.saturating_mul(factor), max);
+
+            // wait for a random duration between half and full backoff
+            let jitter = rand::thread_rng().gen_range(0, backoff / 2 + 1);
+            Duration::from_millis(backoff - jitter)
+        };
+        self.backoff_exponent = self.backoff_exponent.saturating_add(1);
+
+        self.restart_delay = Some(Delay::new(now + backoff));
+        Ok(backoff)
     }
 }
 
+fn duration_as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
 impl Stream for Service {
     type Item = bool;
     type Error = Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Error> {
         loop {
-            if let Some(ref mut process) = self.ethereum_process {
-                match process.poll() {
-                    Ok(Async::Ready(exit_status)) => match self.restart_policy {
-                        RestartPolicy::No => return Ok(Async::Ready(Some(exit_status.success()))),
-                        RestartPolicy::OnFailure | RestartPolicy::Always => {
-                            self.restart();
-                            return Ok(Async::Ready(Some(exit_status.success())));
-                        }
-                    },
-                    Ok(Async::NotReady) => {
-                        return Ok(Async::NotReady);
-                    }
+            self.poll_output();
+
+            if let Some(ref mut delay) = self.restart_delay {
+                match delay.poll() {
+                    Ok(Async::Ready(_)) => {}
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(err) => return Err(Error::from(err)),
+                }
+            }
+            if self.restart_delay.take().is_some() {
+                info!(target: "ethereum", "restart Ethereum client");
+                self.restart()?;
+                continue;
+            }
+
+            let grace_period_is_over = match self.kill_delay {
+                Some(ref mut delay) => match delay.poll() {
+                    Ok(Async::Ready(_)) => true,
+                    Ok(Async::NotReady) => false,
                     Err(err) => return Err(Error::from(err)),
+                },
+                None => false,
+            };
+            if grace_period_is_over {
+                warn!(target: "ethereum", "Ethereum client did not exit within grace period");
+                self.kill();
+            }
+
+            if self.exit_status.is_none() {
+                let exit_status = match self.ethereum_process {
+                    Some(ref mut process) => match process.poll() {
+                        Ok(Async::Ready(exit_status)) => exit_status,
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Err(err) => return Err(Error::from(err)),
+                    },
+                    None => return Ok(Async::Ready(None)),
+                };
+                self.ethereum_process = None;
+                self.running.store(false, Ordering::SeqCst);
+                self.exit_status = Some(exit_status);
+                self.drain_delay = Some(Delay::new(Instant::now() + OUTPUT_DRAIN_TIMEOUT));
+            }
+
+            // log remaining output before reporting exit
+            if self.output.is_some() {
+                let drain_timeout = match self.drain_delay {
+                    Some(ref mut delay) => match delay.poll() {
+                        Ok(Async::Ready(_)) => true,
+                        Ok(Async::NotReady) => false,
+                        Err(err) => return Err(Error::from(err)),
+                    },
+                    None => true,
+                };
+                if !drain_timeout {
+                    return Ok(Async::NotReady);
                 }
+                self.output = None;
+            }
+            self.drain_delay = None;
+            let exit_status = self.exit_status.take().expect("client has exited; qed");
+
+            if self.stopping {
+                info!(target: "ethereum", "Ethereum client stopped with {}", exit_status);
+                self.kill_delay = None;
+                return Ok(Async::Ready(None));
+            }
+
+            let success = exit_status.success();
+            if self.should_restart(success) {
+                let backoff = self.schedule_restart()?;
+                warn!(target: "ethereum", "Ethereum client exited with {}, restart in {:?}",
+                      exit_status, backoff);
+            } else {
+                info!(target: "ethereum", "Ethereum client exited with {}, restart policy is {:?}",
+                      exit_status, self.restart.policy);
             }
 
-            return Ok(Async::Ready(Some(true)));
+            return Ok(Async::Ready(Some(success)));
         }
     }
 }
@@ -0,0 +1,8 @@
+error_chain! {
+    foreign_links {
+        Hyper(hyper::Error);
+    }
+
+    errors {
+    }
+}
@@ -0,0 +1,196 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::future::{self, Either};
+use futures::Future;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::Value as JsonValue;
+
+use super::bootnode::JsonRpcClient;
+use super::metrics::{self, Metrics};
+
+mod error;
+
+pub use self::error::Error;
+
+pub type HealthServer = Box<Future<Item = (), Error = hyper::Error> + Send>;
+
+type ResponseFuture = Box<Future<Item = Response<Body>, Error = hyper::Error> + Send>;
+
+/// checks behind `/healthz` and `/readyz`, and metrics behind `/metrics`
+#[derive(Clone)]
+pub struct HealthCheck {
+    client_running: Arc<AtomicBool>,
+    jsonrpc_client: Arc<JsonRpcClient>,
+    metrics: Arc<Metrics>,
+
+    /// maximum age of latest block, only checked on miners
+    max_block_age: Option<Duration>,
+}
+
+impl HealthCheck {
+    pub fn new(
+        client_running: Arc<AtomicBool>,
+        jsonrpc_client: JsonRpcClient,
+        metrics: Arc<Metrics>,
+        max_block_age: Option<Duration>,
+    ) -> HealthCheck {
+        HealthCheck {
+            client_running,
+            jsonrpc_client: Arc::new(jsonrpc_client),
+            metrics,
+            max_block_age,
+        }
+    }
+
+    fn handle(&self, req: Request<Body>) -> ResponseFuture {
+        match (req.method(), req.uri().path()) {
+            (&Method::GET, "/healthz") => Box::new(future::ok(self.liveness())),
+            (&Method::GET, "/readyz") => self.readiness(),
+            (&Method::GET, "/metrics") => self.metrics(),
+            _ => Box::new(future::ok(response(
+                StatusCode::NOT_FOUND,
+                json!({ "error": "not found" }),
+            ))),
+        }
+    }
+
+    /// launcher is serving this request, so only Ethereum client is checked
+    fn liveness(&self) -> Response<Body> {
+        let running = self.client_running.load(Ordering::SeqCst);
+        let status = if running {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        response(status, json!({ "launcher": true, "client": running }))
+    }
+
+    /// Ethereum client has peers, is not syncing and, on miners, seals blocks
+    fn readiness(&self) -> ResponseFuture {
+        if !self.client_running.load(Ordering::SeqCst) {
+            return Box::new(future::ok(not_ready(vec![
+                "Ethereum client is not running".to_owned(),
+            ])));
+        }
+
+        let client = &self.jsonrpc_client;
+        let latest_block_timestamp = match self.max_block_age {
+            Some(_) => Either::A(client.latest_block_timestamp().map(Some)),
+            None => Either::B(future::ok(None)),
+        };
+
+        let max_block_age = self.max_block_age;
+        Box::new(
+            client
+                .net_peer_count()
+                .join3(client.eth_syncing(), latest_block_timestamp)
+                .then(move |result| {
+                    let (peers, syncing, timestamp) = match result {
+                        Ok(result) => result,
+                        Err(err) => {
+                            return Ok(not_ready(vec![format!(
+                                "failed to query Ethereum client: {}",
+                                err
+                            )]));
+                        }
+                    };
+                    let block_age = timestamp.map(block_age);
+
+                    let mut reasons = Vec::new();
+                    if peers == 0 {
+                        reasons.push("no peers".to_owned());
+                    }
+                    if syncing {
+                        reasons.push("syncing".to_owned());
+                    }
+                    if let (Some(age), Some(max_age)) = (block_age, max_block_age) {
+                        if age > max_age {
+                            reasons.push(format!(
+                                "latest block is sealed {}s ago, expected within {}s",
+                                age.as_secs(),
+                                max_age.as_secs()
+                            ));
+                        }
+                    }
+
+                    let status = if reasons.is_empty() {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    };
+                    Ok(response(
+                        status,
+                        json!({
+                            "ready": reasons.is_empty(),
+                            "peers": peers,
+                            "syncing": syncing,
+                            "latestBlockAge": block_age.map(|age| age.as_secs()),
+                            "reasons": reasons
+                        }),
+                    ))
+                }),
+        )
+    }
+
+    /// launcher metrics, and chain metrics if Ethereum client answers JSON RPC
+    fn metrics(&self) -> ResponseFuture {
+        let chain_metrics = if self.client_running.load(Ordering::SeqCst) {
+            Either::A(metrics::chain_metrics(&self.jsonrpc_client).then(|result| {
+                if let Err(ref err) = result {
+                    debug!(target: "health", "failed to query chain metrics: {}", err);
+                }
+                Ok(result.ok())
+            }))
+        } else {
+            Either::B(future::ok(None))
+        };
+
+        let metrics = self.metrics.clone();
+        Box::new(chain_metrics.map(move |chain| {
+            Response::builder()
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .body(Body::from(metrics.render(chain)))
+                .expect("response builder; qed")
+        }))
+    }
+}
+
+/// serve `/healthz`, `/readyz` and `/metrics` on `port` of every interface
+pub fn serve(port: u16, check: HealthCheck) -> Result<HealthServer, Error> {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port);
+    let server = Server::try_bind(&addr)?.serve(move || {
+        let check = check.clone();
+        service_fn(move |req| check.handle(req))
+    });
+
+    info!(target: "health", "serve health and metrics endpoints on {}", addr);
+    Ok(Box::new(server))
+}
+
+/// time since block `timestamp`
+fn block_age(timestamp: u64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0);
+    Duration::from_secs(now.saturating_sub(timestamp))
+}
+
+fn not_ready(reasons: Vec<String>) -> Response<Body> {
+    response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        json!({ "ready": false, "reasons": reasons }),
+    )
+}
+
+fn response(status: StatusCode, body: JsonValue) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("response builder; qed")
+}
@@ -0,0 +1,155 @@
+use serde_json::{Map, Value as JsonValue};
+
+use super::{ForkSchedule, HardFork};
+
+/// precompiled contracts as `(address, activating hard fork, Parity builtin)`,
+/// contracts without hard fork are active since genesis
+fn precompiles(forks: &ForkSchedule) -> Vec<(u8, Option<HardFork>, JsonValue)> {
+    let mut precompiles = vec![
+        (
+            1,
+            None,
+            json!({
+                "name": "ecrecover",
+                "pricing": { "linear": { "base": 3000, "word": 0 } }
+            }),
+        ),
+        (
+            2,
+            None,
+            json!({
+                "name": "sha256",
+                "pricing": { "linear": { "base": 60, "word": 12 } }
+            }),
+        ),
+        (
+            3,
+            None,
+            json!({
+                "name": "ripemd160",
+                "pricing": { "linear": { "base": 600, "word": 120 } }
+            }),
+        ),
+        (
+            4,
+            None,
+            json!({
+                "name": "identity",
+                "pricing": { "linear": { "base": 15, "word": 3 } }
+            }),
+        ),
+        (
+            5,
+            Some(HardFork::Byzantium),
+            json!({
+                "name": "modexp",
+                "pricing": { "modexp": { "divisor": 20 } }
+            }),
+        ),
+        (
+            6,
+            Some(HardFork::Byzantium),
+            json!({
+                "name": "alt_bn128_add",
+                "pricing": {
+                    "alt_bn128_const_operations": {
+                        "price": 500,
+                        "eip1108_transition_price": 150
+                    }
+                }
+            }),
+        ),
+        (
+            7,
+            Some(HardFork::Byzantium),
+            json!({
+                "name": "alt_bn128_mul",
+                "pricing": {
+                    "alt_bn128_const_operations": {
+                        "price": 40000,
+                        "eip1108_transition_price": 6000
+                    }
+                }
+            }),
+        ),
+        (
+            8,
+            Some(HardFork::Byzantium),
+            json!({
+                "name": "alt_bn128_pairing",
+                "pricing": {
+                    "alt_bn128_pairing": {
+                        "base": 100000,
+                        "pair": 80000,
+                        "eip1108_transition_base": 45000,
+                        "eip1108_transition_pair": 34000
+                    }
+                }
+            }),
+        ),
+        (
+            9,
+            Some(HardFork::Istanbul),
+            json!({
+                "name": "blake2_f",
+                "pricing": { "blake2_f": { "gas_per_round": 1 } }
+            }),
+        ),
+    ];
+
+    // EIP-1108 reprices alt_bn128 in Istanbul
+    if let Some(istanbul) = forks.activation(HardFork::Istanbul) {
+        for (address, _, builtin) in precompiles.iter_mut() {
+            if 6 <= *address && *address <= 8 {
+                builtin["eip1108_transition"] = json!(istanbul);
+            }
+        }
+    }
+
+    precompiles
+}
+
+/// precompiled contracts activated at some block of this fork schedule
+fn activated_precompiles(forks: &ForkSchedule) -> Vec<(u8, Option<u64>, JsonValue)> {
+    precompiles(forks)
+        .into_iter()
+        .filter_map(|(address, fork, builtin)| match fork {
+            None => Some((address, None, builtin)),
+            Some(fork) => forks
+                .activation(fork)
+                .map(|block| (address, Some(block), builtin)),
+        })
+        .collect()
+}
+
+/// precompiled contract address without `0x` prefix
+fn precompile_address(address: u8) -> String {
+    format!("{:040x}", address)
+}
+
+/// builtin accounts of Parity chain spec
+pub fn parity_builtin_accounts(forks: &ForkSchedule) -> Map<String, JsonValue> {
+    activated_precompiles(forks)
+        .into_iter()
+        .map(|(address, activate_at, mut builtin)| {
+            if let Some(block) = activate_at {
+                builtin["activate_at"] = json!(block);
+            }
+            (
+                format!("0x{}", precompile_address(address)),
+                json!({
+                    "balance": "1",
+                    "builtin": builtin
+                }),
+            )
+        })
+        .collect()
+}
+
+/// precompiled contract accounts of go-ethereum genesis, funded with 1 wei
+pub fn geth_precompile_alloc(forks: &ForkSchedule) -> Map<String, JsonValue> {
+    activated_precompiles(forks)
+        .into_iter()
+        .map(|(address, _, _)| (precompile_address(address), json!({ "balance": "0x1" })))
+        .collect()
+}
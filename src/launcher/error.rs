@@ -2,11 +2,36 @@ error_chain! {
     foreign_links {
         StdIo(std::io::Error);
         EmeraldKeyStore(emerald::keystore::Error);
+        EmeraldKeyFile(emerald::keystore::SerializeError);
         SerdeJson(serde_json::Error);
         Type(super::types::Error);
+        EthKey(ethkey::Error);
     }
 
     errors {
-
+        InitializeDatabaseFailed(exit_code: Option<i32>) {
+            description("Failed to initialize chain database")
+            display("Failed to initialize chain database, exit code: {:?}", exit_code)
+        }
+        ImportSealerKeyFailed(exit_code: Option<i32>) {
+            description("Failed to import sealer key")
+            display("Failed to import sealer key, exit code: {:?}", exit_code)
+        }
+        InvalidConsensusEngine(engine: String, program: String) {
+            description("Consensus engine is not supported by Ethereum client")
+            display("Consensus engine {} is not supported by {}", engine, program)
+        }
+        SealerPassphraseMismatch(path: std::path::PathBuf) {
+            description("Sealer passphrase does not match sealer key file")
+            display("Sealer key file {:?} cannot be decrypted with the configured sealer passphrase, restore the passphrase it was created with", path)
+        }
+        InvalidNodeKeyFile(path: std::path::PathBuf) {
+            description("Invalid node key file")
+            display("Invalid node key file {:?}, expected hex encoded secret", path)
+        }
+        GenesisChanged(previous: String, current: String) {
+            description("Genesis of existing chain database has changed")
+            display("Genesis of existing chain database has changed, previous hash: {}, current hash: {}", previous, current)
+        }
     }
 }
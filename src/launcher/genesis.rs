@@ -0,0 +1,204 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde_json::Value as JsonValue;
+use tiny_keccak::{Hasher, Keccak};
+
+use super::{Error, ErrorKind, EthereumProgram};
+
+fn genesis_inputs_hash_file_path(db_path: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(db_path);
+    path.push("genesis-inputs.hash");
+    path
+}
+
+/// `object` without the keys `remove` matches
+fn without_keys<F: Fn(&str) -> bool>(object: &JsonValue, remove: F) -> JsonValue {
+    match object.as_object() {
+        Some(object) => JsonValue::Object(
+            object
+                .iter()
+                .filter(|(key, _)| !remove(key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        None => JsonValue::Null,
+    }
+}
+
+/// parts of rendered chain spec or genesis which determine the genesis block and the chain rules
+/// it is bound to: genesis header, genesis state, validators and chain parameters such as chain
+/// ID or account start nonce, but not chain name, engine parameters or fork transitions
+fn genesis_inputs(spec: &JsonValue, program: EthereumProgram) -> JsonValue {
+    match program {
+        EthereumProgram::Parity => {
+            // builtin pricing and activation are fork transitions, balances are genesis state
+            let accounts: serde_json::Map<String, JsonValue> = spec["accounts"]
+                .as_object()
+                .map(|accounts| {
+                    accounts
+                        .iter()
+                        .map(|(address, account)| {
+                            let mut account = account.clone();
+                            if let Some(account) = account.as_object_mut() {
+                                account.remove("builtin");
+                            }
+                            (address.clone(), account)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            json!({
+                "genesis": spec["genesis"],
+                "params": without_keys(&spec["params"], |key| key.ends_with("Transition")),
+                "accounts": accounts,
+                "validators": spec["engine"]["authorityRound"]["params"]["validators"]
+            })
+        }
+        EthereumProgram::GoEthereum => {
+            // everything but `config` is genesis header or `alloc`, Clique signers are in
+            // `extraData`, `config` holds Clique parameters and fork blocks besides chain ID
+            let mut genesis = spec.clone();
+            genesis["config"] = without_keys(&spec["config"], |key| {
+                key == "clique" || key.ends_with("Block")
+            });
+            genesis
+        }
+    }
+}
+
+/// Keccak-256 of genesis inputs of rendered chain spec or genesis, it is not the genesis block hash
+pub fn genesis_inputs_hash(spec: &JsonValue, program: EthereumProgram) -> String {
+    let mut hasher = Keccak::v256();
+    hasher.update(genesis_inputs(spec, program).to_string().as_bytes());
+
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// whether Ethereum client has created its chain database
+pub fn chain_db_exists(db_path: &PathBuf, program: EthereumProgram) -> bool {
+    let mut path = PathBuf::from(db_path);
+    match program {
+        EthereumProgram::Parity => path.push("chains"),
+        EthereumProgram::GoEthereum => path.push("geth/chaindata"),
+    }
+    path.exists()
+}
+
+/// compare genesis inputs hash of `spec` with the one recorded for existing chain database
+pub fn check_genesis_inputs_hash(
+    db_path: &PathBuf,
+    program: EthereumProgram,
+    spec: &JsonValue,
+) -> Result<String, Error> {
+    let hash = genesis_inputs_hash(spec, program);
+
+    let path = genesis_inputs_hash_file_path(db_path);
+    if path.exists() {
+        let previous = std::fs::read_to_string(path)?.trim().to_owned();
+        if previous != hash && chain_db_exists(db_path, program) {
+            return Err(Error::from(ErrorKind::GenesisChanged(previous, hash)));
+        }
+    } else if chain_db_exists(db_path, program) {
+        warn!(target: "launcher",
+              "chain database exists without genesis inputs hash, assume its genesis is {}", hash);
+    }
+
+    Ok(hash)
+}
+
+pub fn create_genesis_inputs_hash_file(db_path: &PathBuf, hash: &String) -> Result<PathBuf, Error> {
+    let path = genesis_inputs_hash_file_path(db_path);
+    std::fs::File::create(path.clone())?.write_all(hash.as_bytes())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parity_spec() -> JsonValue {
+        json!({
+            "name": "test",
+            "genesis": { "gasLimit": "0x7a1200", "difficulty": "0x1" },
+            "params": {
+                "accountStartNonce": "0x0",
+                "gasLimitBoundDivisor": "0x400",
+                "chainID": "0x11",
+                "eip1283Transition": 100
+            },
+            "engine": {
+                "authorityRound": {
+                    "params": {
+                        "stepDuration": "7",
+                        "validators": { "list": ["0x1111111111111111111111111111111111111111"] }
+                    }
+                }
+            },
+            "accounts": {
+                "0x0000000000000000000000000000000000000001": {
+                    "balance": "0x1",
+                    "builtin": { "name": "ecrecover", "activate_at": 0 }
+                }
+            }
+        })
+    }
+
+    fn parity_hash(spec: &JsonValue) -> String {
+        genesis_inputs_hash(spec, EthereumProgram::Parity)
+    }
+
+    #[test]
+    fn params_change_genesis_inputs_hash() {
+        let spec = parity_spec();
+
+        let mut other = parity_spec();
+        other["params"]["accountStartNonce"] = json!("0x100000");
+        assert_ne!(parity_hash(&spec), parity_hash(&other));
+
+        let mut other = parity_spec();
+        other["params"]["gasLimitBoundDivisor"] = json!("0x800");
+        assert_ne!(parity_hash(&spec), parity_hash(&other));
+    }
+
+    #[test]
+    fn transitions_and_engine_parameters_keep_genesis_inputs_hash() {
+        let spec = parity_spec();
+
+        let mut other = parity_spec();
+        other["name"] = json!("renamed");
+        other["params"]["eip1283Transition"] = json!(200);
+        other["params"]["eip1344Transition"] = json!(300);
+        other["engine"]["authorityRound"]["params"]["stepDuration"] = json!("5");
+        other["accounts"]["0x0000000000000000000000000000000000000001"]["builtin"]["activate_at"] =
+            json!(10);
+        assert_eq!(parity_hash(&spec), parity_hash(&other));
+    }
+
+    #[test]
+    fn geth_fork_blocks_keep_genesis_inputs_hash() {
+        let genesis = json!({
+            "config": { "chainId": 17, "clique": { "period": 7 }, "istanbulBlock": 100 },
+            "extraData": "0x00",
+            "alloc": {}
+        });
+        let hash = genesis_inputs_hash(&genesis, EthereumProgram::GoEthereum);
+
+        let mut other = genesis.clone();
+        other["config"]["istanbulBlock"] = json!(200);
+        other["config"]["clique"]["period"] = json!(5);
+        assert_eq!(
+            hash,
+            genesis_inputs_hash(&other, EthereumProgram::GoEthereum)
+        );
+
+        other["config"]["chainId"] = json!(18);
+        assert_ne!(
+            hash,
+            genesis_inputs_hash(&other, EthereumProgram::GoEthereum)
+        );
+    }
+}
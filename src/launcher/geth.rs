@@ -1,15 +1,321 @@
 use std::path::PathBuf;
 
-use super::Error;
+use ethereum_types::{Address, U256};
+use serde_json::Value as JsonValue;
 
-#[allow(dead_code)]
-pub fn create_genesis_file() -> Result<PathBuf, Error> {
-    let path = PathBuf::new();
-    Ok(path)
+use super::builtins;
+use super::{
+    ChainParameters, ConsensusEngine, EthereumNodeUrl, GenesisAccount, HardFork, NodeType,
+};
+use super::{Error, ErrorKind};
+
+const CLIQUE_EXTRA_VANITY_LENGTH: usize = 32;
+const CLIQUE_EXTRA_SEAL_LENGTH: usize = 65;
+
+pub fn keystore_dir_path(config_dir_path: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(config_dir_path);
+    path.push("keystore");
+    path
+}
+
+/// node key which go-ethereum generated in its data directory
+pub fn node_key_file_path(db_path: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(db_path);
+    path.push("geth/nodekey");
+    path
+}
+
+pub fn genesis_file_path(config_dir: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(config_dir);
+    path.push("genesis.json");
+    path
+}
+
+/// encode signers into Clique `extraData`: 32 bytes vanity, signer addresses, 65 bytes seal
+fn clique_extra_data(signers: &Vec<Address>) -> String {
+    let signers = signers.iter().fold(String::new(), |mut s, signer| {
+        s.push_str(format!("{:x}", signer).as_str());
+        s
+    });
+
+    format!(
+        "0x{}{}{}",
+        "00".repeat(CLIQUE_EXTRA_VANITY_LENGTH),
+        signers,
+        "00".repeat(CLIQUE_EXTRA_SEAL_LENGTH)
+    )
 }
 
-#[allow(dead_code)]
-pub fn create_spec_file() -> Result<PathBuf, Error> {
-    let path = PathBuf::new();
+pub fn genesis(
+    chain: &ChainParameters,
+    consensus_engine: &ConsensusEngine,
+    validators: &Vec<Address>,
+    genesis_accounts: &Vec<GenesisAccount>,
+    genesis_gas_limit: U256,
+) -> Result<JsonValue, Error> {
+    let (engine, extra_data) = match consensus_engine {
+        ConsensusEngine::GethClique { block_period } => (
+            json!({
+                "period": block_period,
+                "epoch": 30000
+            }),
+            clique_extra_data(validators),
+        ),
+        ConsensusEngine::Ethash { .. } | ConsensusEngine::ParityAura { .. } => {
+            return Err(Error::from(ErrorKind::InvalidConsensusEngine(
+                format!("{:?}", consensus_engine),
+                "go-ethereum".to_owned(),
+            )));
+        }
+    };
+
+    let mut genesis = json!({
+        "config": {
+            "chainId": chain.chain_id,
+            "eip150Hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "clique": engine
+        },
+        "nonce": "0x0",
+        "timestamp": "0x0",
+        "extraData": extra_data,
+        "gasLimit": format!("0x{:x}", genesis_gas_limit),
+        "difficulty": "0x1",
+        "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "coinbase": "0x0000000000000000000000000000000000000000",
+        "number": "0x0",
+        "gasUsed": "0x0",
+        "parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "alloc": builtins::geth_precompile_alloc(&chain.forks)
+    });
+
+    let config = genesis["config"]
+        .as_object_mut()
+        .expect("config is an object; qed");
+    let fork_blocks = vec![
+        (HardFork::Homestead, vec!["homesteadBlock"]),
+        (HardFork::TangerineWhistle, vec!["eip150Block"]),
+        (HardFork::SpuriousDragon, vec!["eip155Block", "eip158Block"]),
+        (HardFork::Byzantium, vec!["byzantiumBlock"]),
+        (HardFork::Constantinople, vec!["constantinopleBlock"]),
+        (HardFork::Petersburg, vec!["petersburgBlock"]),
+        (HardFork::Istanbul, vec!["istanbulBlock"]),
+    ];
+    for (fork, keys) in fork_blocks {
+        if let Some(block) = chain.forks.activation(fork) {
+            for key in keys {
+                config.insert(key.into(), json!(block));
+            }
+        }
+    }
+
+    let alloc = genesis["alloc"]
+        .as_object_mut()
+        .expect("alloc is an object; qed");
+    for account in genesis_accounts {
+        alloc.insert(format!("{:x}", account.address), account.to_json());
+    }
+
+    Ok(genesis)
+}
+
+pub fn create_genesis_file(
+    config_dir: &PathBuf,
+    chain: &ChainParameters,
+    consensus_engine: &ConsensusEngine,
+    validators: &Vec<Address>,
+    genesis_accounts: &Vec<GenesisAccount>,
+    genesis_gas_limit: U256,
+) -> Result<PathBuf, Error> {
+    let path = genesis_file_path(config_dir);
+    let genesis = genesis(
+        chain,
+        consensus_engine,
+        validators,
+        genesis_accounts,
+        genesis_gas_limit,
+    )?;
+
+    serde_json::to_writer(
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path.clone())?,
+        &genesis,
+    )?;
     Ok(path)
 }
+
+/// command line of go-ethereum, miners unlock the sealer key with `sealer_passphrase_file_path`
+pub fn command_args(
+    config_file_path: &PathBuf,
+    node_key_file_path: &PathBuf,
+    sealer_passphrase_file_path: Option<&PathBuf>,
+) -> Vec<String> {
+    let mut args = vec![
+        format!(
+            "--config={}",
+            config_file_path.to_str().expect("config file path")
+        ),
+        format!(
+            "--nodekey={}",
+            node_key_file_path.to_str().expect("node key file path")
+        ),
+    ];
+    if let Some(passphrase_file_path) = sealer_passphrase_file_path {
+        // go-ethereum 1.9 refuses to unlock accounts while HTTP JSON-RPC is exposed
+        args.extend(vec![
+            "--mine".into(),
+            "--unlock=0".into(),
+            format!(
+                "--password={}",
+                passphrase_file_path
+                    .to_str()
+                    .expect("sealer passphrase file path")
+            ),
+            "--allow-insecure-unlock".into(),
+        ]);
+    }
+    args
+}
+
+#[derive(Debug, Clone)]
+pub struct GethConfig {
+    pub db_path: String,
+    pub node_type: NodeType,
+
+    pub identity: String,
+    pub network_id: u64,
+
+    pub bootnodes: Vec<EthereumNodeUrl>,
+    pub keystore_dir_path: String,
+
+    pub etherbase: Option<Address>,
+
+    pub ipc_path: String,
+    pub network_port: u16,
+    pub http_jsonrpc_port: u16,
+    pub websocket_jsonrpc_port: u16,
+}
+
+impl GethConfig {
+    pub fn toml_config(&self) -> toml::Value {
+        let db_path = self.db_path.clone();
+        let identity = self.identity.clone();
+        let network_id = self.network_id as i64;
+        let keystore_dir = self.keystore_dir_path.clone();
+        let static_nodes: Vec<_> = self
+            .bootnodes
+            .iter()
+            .map(EthereumNodeUrl::to_string)
+            .collect();
+        let bootstrap_nodes = static_nodes.clone();
+        let ipc_path = self.ipc_path.clone();
+        let listen_addr = format!(":{}", self.network_port);
+        let http_jsonrpc_port = i64::from(self.http_jsonrpc_port);
+        let websocket_jsonrpc_port = i64::from(self.websocket_jsonrpc_port);
+
+        let mut config = toml! {
+            [Eth]
+            NetworkId = network_id
+            SyncMode = "full"
+
+            [Node]
+            DataDir = db_path
+            UserIdent = identity
+            KeyStoreDir = keystore_dir
+            IPCPath = ipc_path
+            HTTPHost = "0.0.0.0"
+            HTTPPort = http_jsonrpc_port
+            HTTPVirtualHosts = ["*"]
+            HTTPModules = ["eth", "net", "web3"]
+            WSHost = "0.0.0.0"
+            WSPort = websocket_jsonrpc_port
+            WSOrigins = ["*"]
+            WSModules = ["eth", "net", "web3"]
+
+            [Node.P2P]
+            ListenAddr = listen_addr
+            StaticNodes = static_nodes
+            BootstrapNodes = bootstrap_nodes
+        };
+
+        if let (NodeType::Miner { .. }, Some(etherbase)) = (&self.node_type, self.etherbase) {
+            config["Eth"]
+                .as_table_mut()
+                .expect("Eth section is a table; qed")
+                .insert(
+                    "Etherbase".into(),
+                    toml::Value::String(format!("0x{:x}", etherbase)),
+                );
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clique_extra_data_layout() {
+        let signers = vec![Address::from([0x11u8; 20]), Address::from([0x22u8; 20])];
+        let extra_data = clique_extra_data(&signers);
+
+        assert!(extra_data.starts_with("0x"));
+        let extra_data = &extra_data[2..];
+        assert_eq!(
+            extra_data.len(),
+            2 * (CLIQUE_EXTRA_VANITY_LENGTH + 2 * 20 + CLIQUE_EXTRA_SEAL_LENGTH)
+        );
+        assert_eq!(&extra_data[..64], "00".repeat(32).as_str());
+        assert_eq!(&extra_data[64..104], "11".repeat(20).as_str());
+        assert_eq!(&extra_data[104..144], "22".repeat(20).as_str());
+        assert_eq!(&extra_data[144..], "00".repeat(65).as_str());
+    }
+
+    #[test]
+    fn clique_extra_data_without_signers() {
+        assert_eq!(
+            clique_extra_data(&vec![]),
+            format!("0x{}", "00".repeat(32 + 65))
+        );
+    }
+
+    #[test]
+    fn miner_unlocks_sealer_key() {
+        let args = command_args(
+            &PathBuf::from("/etc/geth/config.toml"),
+            &PathBuf::from("/etc/geth/nodekey"),
+            Some(&PathBuf::from("/etc/geth/passphrase")),
+        );
+        assert_eq!(
+            args,
+            vec![
+                "--config=/etc/geth/config.toml",
+                "--nodekey=/etc/geth/nodekey",
+                "--mine",
+                "--unlock=0",
+                "--password=/etc/geth/passphrase",
+                "--allow-insecure-unlock",
+            ]
+        );
+    }
+
+    #[test]
+    fn transactor_does_not_unlock() {
+        let args = command_args(
+            &PathBuf::from("/etc/geth/config.toml"),
+            &PathBuf::from("/etc/geth/nodekey"),
+            None,
+        );
+        assert_eq!(
+            args,
+            vec![
+                "--config=/etc/geth/config.toml",
+                "--nodekey=/etc/geth/nodekey"
+            ]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+use ethkey::{KeyPair, Secret};
+
+use emerald::keystore::{Kdf, KeyFile};
+use emerald::PrivateKey;
+
+use super::{Error, ErrorKind, Passphrase};
+
+const RANDOM_PASSPHRASE_LENGTH: usize = 32;
+
+pub fn sealer_passphrase_file_path(config_dir: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(config_dir);
+    path.push("sealer_passphrase");
+    path
+}
+
+pub fn sealer_key_file_path(key_dir_path: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(key_dir_path);
+    path.push("signer_keyfile.json");
+    path
+}
+
+pub fn create_sealer_key_file(
+    key_dir_path: &PathBuf,
+    sealer_private_key: &KeyPair,
+    passphrase: &Passphrase,
+) -> Result<PathBuf, Error> {
+    let secret: [u8; 32] = (**sealer_private_key.secret()).into();
+    let private_key = PrivateKey::from(secret);
+
+    let mut rng = rand::thread_rng();
+
+    let keyfile = {
+        let keyfile = KeyFile::new_custom(
+            private_key,
+            passphrase.as_str(),
+            Kdf::default(),
+            &mut rng,
+            None,
+            None,
+        )?;
+        let mut value = serde_json::to_value(keyfile)?;
+        let obj = value
+            .as_object_mut()
+            .expect("keyfile must be an object; qed");
+
+        // remove unused fields
+        obj.remove("name");
+        obj.remove("description");
+        obj.remove("visible");
+
+        value.clone()
+    };
+
+    // encrypted sealer key is readable by owner only, like its passphrase
+    let path = sealer_key_file_path(key_dir_path);
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path.clone())?;
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    serde_json::to_writer(file, &keyfile)?;
+
+    Ok(path)
+}
+
+/// check that existing sealer key file is encrypted with `passphrase`, which the client unlocks
+/// the sealer account with
+pub fn check_sealer_key_file(key_dir_path: &PathBuf, passphrase: &Passphrase) -> Result<(), Error> {
+    let path = sealer_key_file_path(key_dir_path);
+    let keyfile = KeyFile::decode(std::fs::read_to_string(&path)?.as_str())?;
+
+    match keyfile.decrypt_key(passphrase.as_str()) {
+        Ok(_) => Ok(()),
+        Err(emerald::keystore::Error::FailedMacValidation) => {
+            Err(Error::from(ErrorKind::SealerPassphraseMismatch(path)))
+        }
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// generate a random alphanumeric passphrase from OS random source
+pub fn random_passphrase() -> Result<Passphrase, Error> {
+    use rand::Rng;
+
+    let mut rng = rand::OsRng::new()?;
+    Ok(Passphrase::new(
+        rng.gen_ascii_chars()
+            .take(RANDOM_PASSPHRASE_LENGTH)
+            .collect(),
+    ))
+}
+
+/// read passphrase written by previous run, `None` if not existed
+pub fn read_sealer_passphrase_file(config_dir: &PathBuf) -> Result<Option<Passphrase>, Error> {
+    let path = sealer_passphrase_file_path(config_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let passphrase = std::fs::read_to_string(path)?;
+    Ok(Some(Passphrase::new(passphrase)))
+}
+
+/// write passphrase file which is readable by owner only
+pub fn create_sealer_passphrase_file(
+    config_dir: &PathBuf,
+    passphrase: &Passphrase,
+) -> Result<PathBuf, Error> {
+    let path = sealer_passphrase_file_path(config_dir);
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path.clone())?;
+    // `mode` only applies to newly created file
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(passphrase.as_str().as_bytes())?;
+
+    Ok(path)
+}
+
+/// P2P node key of Ethereum client, owned by launcher so that it can sign with it
+pub fn node_key_file_path(config_dir: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(config_dir);
+    path.push("node_key");
+    path
+}
+
+/// read hex encoded node key, `None` if not existed
+pub fn read_node_key_file(path: &PathBuf) -> Result<Option<KeyPair>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let data = std::fs::read_to_string(path)?;
+    let secret = decode_hex(data.trim())
+        .and_then(|bytes| Secret::from_slice(&bytes))
+        .ok_or_else(|| Error::from(ErrorKind::InvalidNodeKeyFile(path.clone())))?;
+    Ok(Some(KeyPair::from_secret(secret)?))
+}
+
+/// write hex encoded node key which is readable by owner only
+pub fn create_node_key_file(config_dir: &PathBuf, key_pair: &KeyPair) -> Result<PathBuf, Error> {
+    let path = node_key_file_path(config_dir);
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path.clone())?;
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(secret_hex(key_pair).as_bytes())?;
+
+    Ok(path)
+}
+
+/// node secret as hex without `0x`, as Ethereum clients read it
+pub fn secret_hex(key_pair: &KeyPair) -> String {
+    let secret: [u8; 32] = (**key_pair.secret()).into();
+    secret.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_start_matches("0x");
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
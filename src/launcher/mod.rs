@@ -1,24 +1,38 @@
+use std::io::Write;
+use std::net::IpAddr;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
 use tokio_process::{Child as ChildProcess, CommandExt};
 
+use ethkey::{Generator, KeyPair, Random};
+use serde_json::Value as JsonValue;
+
 use super::types::{self, *};
 
+mod builtins;
 mod error;
+pub mod genesis;
 mod geth;
+mod keyfile;
 mod parity;
 
 pub use self::error::{Error, ErrorKind};
 
 const PARITY_EXECUTABLE_PATH: &'static str = "parity";
 const GETH_EXECUTABLE_PATH: &'static str = "geth";
-const DEFAULT_SEALER_KEYFILE_PASSPHRASE: &'static str = "0123456789";
 
 pub struct EthereumLauncher {
+    pub chain: ChainParameters,
     pub node_type: NodeType,
     pub engine: ConsensusEngine,
+    pub genesis_accounts: Vec<GenesisAccount>,
+    pub sealer_passphrase: Option<Passphrase>,
     pub bootnodes: Vec<EthereumNodeUrl>,
     pub launcher_parameters: LauncherParameters,
+
+    /// address advertised to peers, the client detects its own if unspecified
+    pub public_ip: IpAddr,
 }
 
 impl EthereumLauncher {
@@ -60,170 +74,418 @@ impl EthereumLauncher {
         path_buf
     }
 
-    pub fn initialize(&self) -> Result<String, Error> {
-        match (self.engine.program(), self.node_type.clone()) {
-            (EthereumProgram::Parity, NodeType::Miner { index, .. }) => {
-                let config_dir = self.config_dir_path();
-                std::fs::create_dir_all(config_dir.clone())?;
+    pub fn spec_file_path(&self) -> PathBuf {
+        let config_dir = self.config_dir_path();
+        match self.engine.program() {
+            EthereumProgram::Parity => parity::spec_file_path(&config_dir),
+            EthereumProgram::GoEthereum => geth::genesis_file_path(&config_dir),
+        }
+    }
 
-                let db_path = self.chain_data_dir_path();
-                std::fs::create_dir_all(db_path.clone())?;
+    /// file which Ethereum client output is written into
+    pub fn client_log_file_path(&self) -> PathBuf {
+        let mut path = self.config_dir_path();
+        path.push(match self.engine.program() {
+            EthereumProgram::Parity => "parity.log",
+            EthereumProgram::GoEthereum => "geth.log",
+        });
+        path
+    }
 
-                let passphrase = String::from(DEFAULT_SEALER_KEYFILE_PASSPHRASE);
+    pub fn identity(&self) -> String {
+        match self.node_type {
+            NodeType::Miner { index, .. } => format!("miner-{}", index),
+            NodeType::Transactor { .. } => "transactor".into(),
+        }
+    }
 
-                let sealer_key_pair = self
-                    .node_type
+    fn sealer_key_pair(&self) -> Result<Option<KeyPair>, Error> {
+        match self.node_type {
+            NodeType::Miner { index, .. } => Ok(Some(
+                self.node_type
                     .validator_keypairs()?
                     .get(index)
                     .expect("index must be valid")
-                    .clone();
-                let key_dir = parity::create_key_directory(&config_dir)?;
-                let key_file_path =
-                    parity::create_sealer_key_file(&key_dir, &sealer_key_pair, &passphrase)?;
+                    .clone(),
+            )),
+            NodeType::Transactor { .. } => Ok(None),
+        }
+    }
 
-                info!(target: "launcher", "create key file {:?} for {:?}",
-                      key_file_path, sealer_key_pair.address());
+    /// configured passphrase, or the one written by previous run, or a random one
+    fn sealer_passphrase(&self, config_dir: &PathBuf) -> Result<Passphrase, Error> {
+        if let Some(ref passphrase) = self.sealer_passphrase {
+            return Ok(passphrase.clone());
+        }
+        if let Some(passphrase) = keyfile::read_sealer_passphrase_file(config_dir)? {
+            info!(target: "launcher", "reuse sealer passphrase of previous run");
+            return Ok(passphrase);
+        }
 
-                let sealer_password_file_path =
-                    parity::create_sealer_passphrase_file(&config_dir, &passphrase)?;
+        info!(target: "launcher", "generate random sealer passphrase");
+        keyfile::random_passphrase()
+    }
 
-                let spec_file_path = parity::create_spec_file(
-                    &config_dir,
-                    &self.engine,
-                    &self.node_type.validators()?,
-                    self.launcher_parameters.genesis_block_gas_limit,
-                )?;
-
-                let reserved_peers_file_path =
-                    parity::create_reserverd_peers_file(&config_dir, &self.bootnodes)?;
-
-                let config_file_path: String = {
-                    let config = parity::ParityConfig {
-                        db_path: db_path.to_str().expect("db directory path").to_owned(),
-                        node_type: self.node_type.clone(),
-
-                        identity: format!("miner-{}", index),
-                        spec_path: spec_file_path.to_str().expect("spec file path").to_owned(),
-                        bootnodes: self.bootnodes.clone(),
-                        reserved_peers_file_path: reserved_peers_file_path
-                            .to_str()
-                            .expect("reserved peers file")
-                            .to_owned(),
-                        force_sealing: true,
-                        sealer_address: sealer_key_pair.address(),
-                        sealer_passphrase_file_path: sealer_password_file_path
-                            .to_str()
-                            .expect("sealer passphrase file path")
-                            .to_owned(),
-                        ipc_path: self.ipc_path().to_str().expect("ipc path").to_owned(),
-                        network_port: self.launcher_parameters.network_port,
-                        http_jsonrpc_port: self.launcher_parameters.http_jsonrpc_port,
-                        websocket_jsonrpc_port: self.launcher_parameters.websocket_jsonrpc_port,
-                    };
-
-                    config
-                        .save(&self.config_file_path())?
+    /// chain spec for Parity or genesis for go-ethereum
+    pub fn spec(&self) -> Result<JsonValue, Error> {
+        let validators = self.node_type.validators()?;
+        let genesis_gas_limit = self.launcher_parameters.genesis_block_gas_limit;
+        match self.engine.program() {
+            EthereumProgram::Parity => parity::spec(
+                &self.chain,
+                &self.engine,
+                &validators,
+                &self.genesis_accounts,
+                genesis_gas_limit,
+            ),
+            EthereumProgram::GoEthereum => geth::genesis(
+                &self.chain,
+                &self.engine,
+                &validators,
+                &self.genesis_accounts,
+                genesis_gas_limit,
+            ),
+        }
+    }
+
+    /// configuration file of Ethereum client
+    pub fn config(&self) -> Result<toml::Value, Error> {
+        let config_dir = self.config_dir_path();
+        let db_path = self.chain_data_dir_path();
+        let sealer_key_pair = self.sealer_key_pair()?;
+
+        match self.engine.program() {
+            EthereumProgram::Parity => {
+                let config = parity::ParityConfig {
+                    db_path: db_path.to_str().expect("db directory path").to_owned(),
+                    node_type: self.node_type.clone(),
+
+                    identity: self.identity(),
+                    spec_path: self
+                        .spec_file_path()
+                        .to_str()
+                        .expect("spec file path")
+                        .to_owned(),
+                    bootnodes: self.bootnodes.clone(),
+                    reserved_peers_file_path: parity::reserved_peers_file_path(&config_dir)
+                        .to_str()
+                        .expect("reserved peers file")
+                        .to_owned(),
+
+                    engine: self.engine,
+                    force_sealing: self.node_type.is_miner(),
+                    sealer_address: sealer_key_pair.map(|key_pair| key_pair.address()),
+                    sealer_passphrase_file_path: keyfile::sealer_passphrase_file_path(&config_dir)
                         .to_str()
-                        .expect("config file path")
-                        .into()
+                        .expect("sealer passphrase file path")
+                        .to_owned(),
+                    node_key: keyfile::read_node_key_file(&keyfile::node_key_file_path(
+                        &config_dir,
+                    ))?
+                    .map(|key_pair| keyfile::secret_hex(&key_pair)),
+                    ipc_path: self.ipc_path().to_str().expect("ipc path").to_owned(),
+                    network_port: self.launcher_parameters.network_port,
+                    http_jsonrpc_port: self.launcher_parameters.http_jsonrpc_port,
+                    websocket_jsonrpc_port: self.launcher_parameters.websocket_jsonrpc_port,
                 };
 
-                Command::new(PARITY_EXECUTABLE_PATH)
-                    .arg(format!("--config={}", config_file_path))
-                    .arg("account")
-                    .arg("import")
-                    .arg(key_dir.to_str().expect("key directory"))
-                    .spawn()?;
+                Ok(config.toml_config())
+            }
+            EthereumProgram::GoEthereum => {
+                let config = geth::GethConfig {
+                    db_path: db_path.to_str().expect("db directory path").to_owned(),
+                    node_type: self.node_type.clone(),
+
+                    identity: self.identity(),
+                    network_id: self.chain.network_id,
+                    bootnodes: self.bootnodes.clone(),
+                    keystore_dir_path: geth::keystore_dir_path(&config_dir)
+                        .to_str()
+                        .expect("keystore directory")
+                        .to_owned(),
+                    etherbase: sealer_key_pair.map(|key_pair| key_pair.address()),
+                    ipc_path: self.ipc_path().to_str().expect("ipc path").to_owned(),
+                    network_port: self.launcher_parameters.network_port,
+                    http_jsonrpc_port: self.launcher_parameters.http_jsonrpc_port,
+                    websocket_jsonrpc_port: self.launcher_parameters.websocket_jsonrpc_port,
+                };
+
+                Ok(config.toml_config())
+            }
+        }
+    }
+
+    /// P2P node key, generated on first start unless go-ethereum has created one before
+    pub fn node_key(&self) -> Result<KeyPair, Error> {
+        let config_dir = self.config_dir_path();
+        let path = keyfile::node_key_file_path(&config_dir);
+        if let Some(key_pair) = keyfile::read_node_key_file(&path)? {
+            return Ok(key_pair);
+        }
 
-                Ok(config_file_path)
+        let existing_key_pair = match self.engine.program() {
+            EthereumProgram::Parity => None,
+            EthereumProgram::GoEthereum => {
+                keyfile::read_node_key_file(&geth::node_key_file_path(&self.chain_data_dir_path()))?
             }
-            (EthereumProgram::Parity, NodeType::Transactor { .. }) => {
-                let config_dir = self.config_dir_path();
-                std::fs::create_dir_all(config_dir.clone())?;
+        };
+        let key_pair = match existing_key_pair {
+            Some(key_pair) => key_pair,
+            None => Random.generate()?,
+        };
+
+        std::fs::create_dir_all(config_dir.clone())?;
+        keyfile::create_node_key_file(&config_dir, &key_pair)?;
+        info!(target: "launcher", "create node key file {:?}", path);
+        Ok(key_pair)
+    }
+
+    /// advertised enode URL of this node, derived from node key, public IP and external P2P port
+    pub fn enode_url(&self) -> Result<EthereumNodeUrl, Error> {
+        Ok(EthereumNodeUrl {
+            node_id: format!("{:x}", self.node_key()?.public()),
+            host: NodeHost::Ip(self.public_ip),
+            port: self.launcher_parameters.external_network_port,
+            discovery_port: None,
+        })
+    }
 
-                let db_path = self.chain_data_dir_path();
-                std::fs::create_dir_all(db_path.clone())?;
+    /// hash of genesis inputs of rendered chain spec or genesis, the same on every
+    /// node of a chain
+    pub fn genesis_inputs_hash(&self) -> Result<String, Error> {
+        Ok(genesis::genesis_inputs_hash(
+            &self.spec()?,
+            self.engine.program(),
+        ))
+    }
 
-                let spec_file_path = parity::create_spec_file(
+    pub fn render_spec(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self.spec()?)?)
+    }
+
+    pub fn render_config(&self) -> Result<String, Error> {
+        Ok(toml::to_string(&self.config()?).expect("config is serializable; qed"))
+    }
+
+    /// render derived configuration on every start, and run one-time actions,
+    /// importing sealer key and initializing chain database, if they are not done yet
+    pub fn initialize(&self) -> Result<String, Error> {
+        let program = self.engine.program();
+
+        let config_dir = self.config_dir_path();
+        std::fs::create_dir_all(config_dir.clone())?;
+
+        let db_path = self.chain_data_dir_path();
+        std::fs::create_dir_all(db_path.clone())?;
+        self.node_key()?;
+
+        // refuse to run an existing chain with different genesis
+        let spec = self.spec()?;
+        let genesis_inputs_hash = genesis::check_genesis_inputs_hash(&db_path, program, &spec)?;
+
+        let sealer = match self.sealer_key_pair()? {
+            Some(sealer_key_pair) => {
+                let passphrase = self.sealer_passphrase(&config_dir)?;
+
+                // the client unlocks the key imported on first start with this passphrase
+                let key_dir = self.sealer_key_dir_path(&config_dir);
+                if keyfile::sealer_key_file_path(&key_dir).exists() {
+                    keyfile::check_sealer_key_file(&key_dir, &passphrase)?;
+                }
+                keyfile::create_sealer_passphrase_file(&config_dir, &passphrase)?;
+                Some((sealer_key_pair, passphrase))
+            }
+            None => None,
+        };
+
+        let validators = self.node_type.validators()?;
+        let genesis_gas_limit = self.launcher_parameters.genesis_block_gas_limit;
+        let genesis_file_path = match program {
+            EthereumProgram::Parity => {
+                parity::create_reserverd_peers_file(&config_dir, &self.bootnodes)?;
+                parity::create_spec_file(
                     &config_dir,
+                    &self.chain,
                     &self.engine,
-                    &self.node_type.validators()?,
-                    self.launcher_parameters.genesis_block_gas_limit,
-                )?;
-
-                let reserved_peers_file_path =
-                    parity::create_reserverd_peers_file(&config_dir, &self.bootnodes)?;
-                let fake_sealer = {
-                    use ethkey::{Brain, Generator};
-                    Brain::new("0123456789".into())
-                        .generate()
-                        .expect("generate keypair from random")
-                };
+                    &validators,
+                    &self.genesis_accounts,
+                    genesis_gas_limit,
+                )?
+            }
+            EthereumProgram::GoEthereum => geth::create_genesis_file(
+                &config_dir,
+                &self.chain,
+                &self.engine,
+                &validators,
+                &self.genesis_accounts,
+                genesis_gas_limit,
+            )?,
+        };
+
+        let config_file_path: String = {
+            let path = self.create_config_file()?;
+            path.to_str().expect("config file path").into()
+        };
+
+        if let Some((sealer_key_pair, passphrase)) = sealer {
+            self.import_sealer_key(&config_dir, &sealer_key_pair, &passphrase)?;
+        }
 
-                let config_file_path: String = {
-                    let config = parity::ParityConfig {
-                        db_path: db_path.to_str().expect("db directory path").to_owned(),
-                        node_type: self.node_type.clone(),
-
-                        identity: "transactor".into(),
-                        spec_path: spec_file_path.to_str().expect("spec file path").to_owned(),
-                        bootnodes: self.bootnodes.clone(),
-                        reserved_peers_file_path: reserved_peers_file_path
-                            .to_str()
-                            .expect("reserved peers file")
-                            .to_owned(),
-
-                        force_sealing: false,
-                        sealer_address: fake_sealer.address(),
-                        sealer_passphrase_file_path: parity::create_sealer_passphrase_file(
-                            &config_dir,
-                            &DEFAULT_SEALER_KEYFILE_PASSPHRASE.into(),
-                        )?
-                        .to_str()
-                        .expect("sealer passphrase file path")
-                        .to_owned(),
-                        ipc_path: self.ipc_path().to_str().expect("ipc path").to_owned(),
-                        network_port: self.launcher_parameters.network_port,
-                        http_jsonrpc_port: self.launcher_parameters.http_jsonrpc_port,
-                        websocket_jsonrpc_port: self.launcher_parameters.websocket_jsonrpc_port,
-                    };
-
-                    config
-                        .save(&self.config_file_path())?
-                        .to_str()
-                        .expect("config file path")
-                        .into()
-                };
+        if let EthereumProgram::GoEthereum = program {
+            if !genesis::chain_db_exists(&db_path, program) {
+                self.init_geth_database(&db_path, &genesis_file_path)?;
+            }
+        }
+
+        genesis::create_genesis_inputs_hash_file(&db_path, &genesis_inputs_hash)?;
+        Ok(config_file_path)
+    }
+
+    /// directory of sealer key file, imported from by Parity and read by go-ethereum
+    fn sealer_key_dir_path(&self, config_dir: &PathBuf) -> PathBuf {
+        match self.engine.program() {
+            EthereumProgram::Parity => parity::key_dir_path(config_dir),
+            EthereumProgram::GoEthereum => geth::keystore_dir_path(config_dir),
+        }
+    }
 
-                Ok(config_file_path)
+    /// create sealer key file and import it into Parity keystore, only once
+    fn import_sealer_key(
+        &self,
+        config_dir: &PathBuf,
+        sealer_key_pair: &KeyPair,
+        passphrase: &Passphrase,
+    ) -> Result<(), Error> {
+        let program = self.engine.program();
+        let key_dir = self.sealer_key_dir_path(config_dir);
+        if keyfile::sealer_key_file_path(&key_dir).exists() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(key_dir.clone())?;
+        let key_file_path = keyfile::create_sealer_key_file(&key_dir, sealer_key_pair, passphrase)?;
+        info!(target: "launcher", "create key file {:?} for {:?}",
+              key_file_path, sealer_key_pair.address());
+
+        if let EthereumProgram::Parity = program {
+            let status = Command::new(PARITY_EXECUTABLE_PATH)
+                .arg(format!(
+                    "--config={}",
+                    self.config_file_path().to_str().expect("config file path")
+                ))
+                .arg("account")
+                .arg("import")
+                .arg(key_dir.to_str().expect("key directory"))
+                .status()?;
+
+            if !status.success() {
+                // import again on next start
+                std::fs::remove_file(key_file_path)?;
+                return Err(Error::from(ErrorKind::ImportSealerKeyFailed(status.code())));
             }
-            (EthereumProgram::GoEthereum, NodeType::Miner { .. }) => {
-                unimplemented!();
+        }
+
+        Ok(())
+    }
+
+    /// config file is readable by owner only, as it contains node key of Parity
+    fn create_config_file(&self) -> Result<PathBuf, Error> {
+        let path = self.config_file_path();
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path.clone())?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(self.render_config()?.as_bytes())?;
+        Ok(path)
+    }
+
+    /// persist `bootnodes` for later restarts, into reserved peers file of Parity
+    /// or static nodes of go-ethereum config
+    pub fn write_peers(&self) -> Result<(), Error> {
+        match self.engine.program() {
+            EthereumProgram::Parity => {
+                parity::create_reserverd_peers_file(&self.config_dir_path(), &self.bootnodes)?;
             }
-            (EthereumProgram::GoEthereum, NodeType::Transactor { .. }) => {
-                unimplemented!();
+            EthereumProgram::GoEthereum => {
+                self.create_config_file()?;
             }
         }
+        Ok(())
+    }
+
+    fn init_geth_database(
+        &self,
+        db_path: &PathBuf,
+        genesis_file_path: &PathBuf,
+    ) -> Result<(), Error> {
+        let status = Command::new(GETH_EXECUTABLE_PATH)
+            .arg(format!(
+                "--datadir={}",
+                db_path.to_str().expect("db directory path")
+            ))
+            .arg("init")
+            .arg(genesis_file_path.to_str().expect("genesis file path"))
+            .status()?;
+
+        if !status.success() {
+            return Err(Error::from(ErrorKind::InitializeDatabaseFailed(
+                status.code(),
+            )));
+        }
+
+        info!(target: "launcher", "initialized geth database {:?} with genesis {:?}",
+              db_path, genesis_file_path);
+        Ok(())
     }
 
     fn execute_command(&self) -> (Command, Vec<String>) {
-        let config_file_path =
-            String::from(self.config_file_path().to_str().expect("config file path"));
-        match self.engine.program() {
+        let config_file_path = self.config_file_path();
+        let (command, mut args) = match self.engine.program() {
             EthereumProgram::Parity => (
                 Command::new(PARITY_EXECUTABLE_PATH),
                 vec![
-                    format!("--config={}", config_file_path),
+                    format!(
+                        "--config={}",
+                        config_file_path.to_str().expect("config file path")
+                    ),
                     "--no-download".into(),
                     "--no-hardware-wallets".into(),
                 ],
             ),
-            EthereumProgram::GoEthereum => (Command::new(GETH_EXECUTABLE_PATH), vec![]),
+            EthereumProgram::GoEthereum => {
+                // the sealer key is the only account in keystore
+                let passphrase_file_path = if self.node_type.is_miner() {
+                    Some(keyfile::sealer_passphrase_file_path(
+                        &self.config_dir_path(),
+                    ))
+                } else {
+                    None
+                };
+                let args = geth::command_args(
+                    &config_file_path,
+                    &keyfile::node_key_file_path(&self.config_dir_path()),
+                    passphrase_file_path.as_ref(),
+                );
+                (Command::new(GETH_EXECUTABLE_PATH), args)
+            }
+        };
+
+        // containers only know their private address
+        if !self.public_ip.is_unspecified() {
+            args.push(format!("--nat=extip:{}", self.public_ip));
         }
+        (command, args)
     }
 
+    /// spawn Ethereum client with stdout and stderr piped
     pub fn execute_async(&self) -> Result<ChildProcess, std::io::Error> {
         let (mut cmd, args) = self.execute_command();
-        cmd.args(args).spawn_async()
+        cmd.args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn_async()
     }
 }
@@ -2,100 +2,96 @@ use std::io::Write;
 use std::path::PathBuf;
 
 use ethereum_types::{Address, U256};
-use ethkey::KeyPair;
-
-use emerald::keystore::{Kdf, KeyFile};
-use emerald::PrivateKey;
-
-use super::Error;
-use super::{ConsensusEngine, EthereumNodeUrl, NodeType};
-
-pub fn create_key_directory(config_dir_path: &PathBuf) -> Result<PathBuf, Error> {
+use serde_json::Value as JsonValue;
+
+use super::builtins;
+use super::{ChainParameters, ConsensusEngine, EthereumNodeUrl, GenesisAccount, NodeType};
+use super::{Error, ErrorKind};
+use super::{HardFork, ETHASH_DURATION_LIMIT};
+
+/// Parity transition params activated by each hard fork, `eip1283` means `eip1283Transition`
+const FORK_TRANSITIONS: &[(HardFork, &[&str])] = &[
+    (HardFork::TangerineWhistle, &["eip150"]),
+    (
+        HardFork::SpuriousDragon,
+        &["eip155", "eip160", "eip161abc", "eip161d", "maxCodeSize"],
+    ),
+    (
+        HardFork::Byzantium,
+        &["eip140", "eip211", "eip214", "eip658"],
+    ),
+    (
+        HardFork::Constantinople,
+        &["eip145", "eip1014", "eip1052", "eip1283"],
+    ),
+    (HardFork::Petersburg, &["eip1283Disable"]),
+    (
+        HardFork::Istanbul,
+        &[
+            "eip1283Reenable",
+            "eip1344",
+            "eip1706",
+            "eip1884",
+            "eip2028",
+        ],
+    ),
+];
+
+pub fn key_dir_path(config_dir_path: &PathBuf) -> PathBuf {
     let mut path = PathBuf::from(config_dir_path);
     path.push("keys");
-
-    std::fs::create_dir(path.clone())?;
-    Ok(path)
+    path
 }
 
-pub fn create_sealer_key_file(
-    key_dir_path: &PathBuf,
-    sealer_private_key: &KeyPair,
-    passphrase: &String,
-) -> Result<PathBuf, Error> {
-    let secret: [u8; 32] = (**sealer_private_key.secret()).into();
-    let private_key = PrivateKey::from(secret);
-
-    let mut rng = rand::thread_rng();
-
-    let keyfile = {
-        let keyfile = KeyFile::new_custom(
-            private_key,
-            passphrase.as_str(),
-            Kdf::default(),
-            &mut rng,
-            None,
-            None,
-        )?;
-        let mut value = serde_json::to_value(keyfile)?;
-        let obj = value
-            .as_object_mut()
-            .expect("keyfile must be an object; qed");
-
-        // remove unused fields
-        obj.remove("name");
-        obj.remove("description");
-        obj.remove("visible");
-
-        value.clone()
-    };
-
-    let mut path = PathBuf::from(key_dir_path);
-    path.push("signer_keyfile.json");
-
-    serde_json::to_writer(
-        std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(path.clone())?,
-        &keyfile,
-    )?;
-
-    Ok(path)
+pub fn spec_file_path(config_dir: &PathBuf) -> PathBuf {
+    let mut path = PathBuf::from(config_dir);
+    path.push("spec.json");
+    path
 }
 
-pub fn create_sealer_passphrase_file(
-    config_dir: &PathBuf,
-    passphrase: &String,
-) -> Result<PathBuf, Error> {
+pub fn reserved_peers_file_path(config_dir: &PathBuf) -> PathBuf {
     let mut path = PathBuf::from(config_dir);
-    path.push("sealer_passphrase");
-
-    std::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(path.clone())?
-        .write(passphrase.as_bytes())?;
-
-    Ok(path)
+    path.push("reserved_peers");
+    path
 }
 
-pub fn create_spec_file(
-    config_dir: &PathBuf,
+pub fn spec(
+    chain: &ChainParameters,
     consensus_engine: &ConsensusEngine,
     validators: &Vec<Address>,
+    genesis_accounts: &Vec<GenesisAccount>,
     genesis_gas_limit: U256,
-) -> Result<PathBuf, Error> {
-    let mut path = PathBuf::from(config_dir);
-    path.push("spec.json");
-
-    let (chain_name, network_id, engine, seal) = match consensus_engine {
+) -> Result<JsonValue, Error> {
+    let (engine, seal, difficulty) = match consensus_engine {
+        ConsensusEngine::Ethash {
+            genesis_difficulty,
+            block_reward,
+        } => (
+            json!({
+                "Ethash": {
+                    "params": {
+                        "minimumDifficulty": format!("0x{:x}", genesis_difficulty),
+                        "difficultyBoundDivisor": "0x800",
+                        "durationLimit": format!("0x{:x}", ETHASH_DURATION_LIMIT),
+                        "blockReward": format!("0x{:x}", block_reward),
+                        "homesteadTransition": chain.forks.transition(HardFork::Homestead),
+                        "eip100bTransition": chain.forks.transition(HardFork::Byzantium),
+                        "bombDefuseTransition": 0
+                    }
+                }
+            }),
+            json!({
+                "ethereum": {
+                    "nonce": "0x0000000000000042",
+                    "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000"
+                }
+            }),
+            *genesis_difficulty,
+        ),
         ConsensusEngine::ParityAura {
             block_period,
             block_reward,
         } => (
-            "ParityAura",
-            U256::from(0x2323),
             json!({
                 "authorityRound": {
                     "params": {
@@ -113,16 +109,20 @@ pub fn create_spec_file(
                 "signature": "0x0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"
             }
             }),
+            U256::one(),
         ),
-        _ => {
-            unimplemented!();
+        ConsensusEngine::GethClique { .. } => {
+            return Err(Error::from(ErrorKind::InvalidConsensusEngine(
+                format!("{:?}", consensus_engine),
+                "Parity".to_owned(),
+            )));
         }
     };
 
-    let spec = json!({
-        "name": chain_name,
+    let mut spec = json!({
+        "name": chain.name,
         "genesis": {
-            "difficulty": "0x1",
+            "difficulty": format!("0x{:x}", difficulty),
             "gasLimit": format!("0x{:x}", genesis_gas_limit),
             "seal": seal
         } ,
@@ -130,74 +130,72 @@ pub fn create_spec_file(
             "maximumExtraDataSize": "0x20",
             "minGasLimit":          "0x1388",
             "gasLimitBoundDivisor": "0x400",
-            "networkID":  format!("0x{:x}", network_id),
-            "eip155Transition": 0,
+            "networkID":  format!("0x{:x}", chain.network_id),
+            "chainID": format!("0x{:x}", chain.chain_id),
             "maxCodeSize": 24576,
-            "maxCodeSizeTransition": 0,
             "validateChainIdTransition": 0,
-            "validateReceiptsTransition": 0,
-            "eip140Transition": 0,
-            "eip211Transition": 0,
-            "eip214Transition": 0,
-            "eip658Transition": 0,
-            "wasmActivationTransition": 0
+            "validateReceiptsTransition": 0
         },
         "engine": engine,
-        "accounts": {
-            "0x0000000000000000000000000000000000000001": {
-                "balance": "1", "builtin": {
-                    "name": "ecrecover",
-                    "pricing": {
-                        "linear": {
-                            "base": 3000,
-                            "word": 0
-                        }
-                    }
-                }
-            },
-            "0x0000000000000000000000000000000000000002": {
-                "balance": "1",
-                "builtin": {
-                    "name": "sha256",
-                    "pricing": {
-                        "linear": {
-                            "base": 60,
-                            "word": 12
-                        }
-                    }
-                }
-            },
-            "0x0000000000000000000000000000000000000003": {
-                "balance": "1",
-                "builtin": {
-                    "name": "ripemd160",
-                    "pricing": {
-                        "linear": {
-                            "base": 600,
-                            "word": 120
-                        }
-                    }
-                }
-            },
-            "0x0000000000000000000000000000000000000004": {
-                "balance": "1",
-                "builtin": {
-                    "name": "identity",
-                    "pricing": {
-                        "linear": {
-                            "base": 15,
-                            "word": 3
-                        }
-                    }
-                }
-            }
-        }
+        "accounts": builtins::parity_builtin_accounts(&chain.forks)
     });
 
+    let params = spec["params"]
+        .as_object_mut()
+        .expect("params is an object; qed");
+    for (fork, eips) in FORK_TRANSITIONS {
+        for eip in eips.iter() {
+            params.insert(
+                format!("{}Transition", eip),
+                json!(chain.forks.transition(*fork)),
+            );
+        }
+    }
+    for (eip, block) in &chain.forks.eip_transitions {
+        params.insert(format!("{}Transition", eip), json!(block));
+    }
+
+    let accounts = spec["accounts"]
+        .as_object_mut()
+        .expect("accounts is an object; qed");
+    for account in genesis_accounts {
+        let entry = accounts
+            .entry(format!("0x{:x}", account.address))
+            .or_insert(json!({}));
+        for (key, value) in account
+            .to_json()
+            .as_object()
+            .expect("account is an object; qed")
+        {
+            entry[key] = value.clone();
+        }
+    }
+
+    Ok(spec)
+}
+
+pub fn create_spec_file(
+    config_dir: &PathBuf,
+    chain: &ChainParameters,
+    consensus_engine: &ConsensusEngine,
+    validators: &Vec<Address>,
+    genesis_accounts: &Vec<GenesisAccount>,
+    genesis_gas_limit: U256,
+) -> Result<PathBuf, Error> {
+    let path = spec_file_path(config_dir);
+    let spec = spec(
+        chain,
+        consensus_engine,
+        validators,
+        genesis_accounts,
+        genesis_gas_limit,
+    )?;
+
     serde_json::to_writer(
         std::fs::OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(path.clone())?,
         &spec,
     )?;
@@ -208,11 +206,10 @@ pub fn create_reserverd_peers_file(
     config_dir: &PathBuf,
     bootnodes: &Vec<EthereumNodeUrl>,
 ) -> Result<PathBuf, Error> {
-    let mut path = PathBuf::from(config_dir);
-    path.push("reserved_peers");
+    let path = reserved_peers_file_path(config_dir);
 
     let data = bootnodes.iter().fold(String::new(), |mut s, url| {
-        s.push_str(url.to_string().as_str());
+        s.push_str(url.without_discovery_port().to_string().as_str());
         s.push('\n');
         s
     });
@@ -220,6 +217,7 @@ pub fn create_reserverd_peers_file(
     std::fs::OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(true)
         .open(path.clone())?
         .write_all(data.as_bytes())?;
     Ok(path)
@@ -236,10 +234,14 @@ pub struct ParityConfig {
     pub bootnodes: Vec<EthereumNodeUrl>,
     pub reserved_peers_file_path: String,
 
+    pub engine: ConsensusEngine,
     pub force_sealing: bool,
-    pub sealer_address: Address,
+    pub sealer_address: Option<Address>,
     pub sealer_passphrase_file_path: String,
 
+    /// hex encoded P2P node key
+    pub node_key: Option<String>,
+
     pub ipc_path: String,
     pub network_port: u16,
     pub http_jsonrpc_port: u16,
@@ -247,21 +249,46 @@ pub struct ParityConfig {
 }
 
 impl ParityConfig {
+    fn mining_config(&self, sealer_address: Address) -> toml::Value {
+        let (engine_signer, author) = {
+            let engine_signer = format!("{:x?}", sealer_address);
+            (engine_signer.clone(), engine_signer)
+        };
+        let force_sealing = self.force_sealing;
+
+        match self.engine {
+            ConsensusEngine::Ethash { .. } => {
+                // PoW, sealer address is used as coinbase only
+                toml! {
+                    author = author
+                    reseal_on_txs = "all"
+                    reseal_min_period = 4000
+                    work_queue_size = 20
+                    usd_per_tx = "0"
+                    force_sealing = force_sealing
+                }
+            }
+            _ => {
+                toml! {
+                    author = author
+                    engine_signer = engine_signer
+                    reseal_on_txs = "none"
+                    usd_per_tx = "0"
+                    force_sealing = force_sealing
+                }
+            }
+        }
+    }
+
     pub fn toml_config(&self) -> toml::Value {
         let db_path = self.db_path.clone();
         let chain = self.spec_path.clone();
         let identity = self.identity.clone();
-        let (engine_signer, author, unlock) = {
-            let engine_signer = format!("{:x?}", self.sealer_address);
-            (engine_signer.clone(), engine_signer.clone(), engine_signer)
-        };
-        let force_sealing = self.force_sealing;
 
-        let password = self.sealer_passphrase_file_path.clone();
         let bootnodes: Vec<_> = self
             .bootnodes
             .iter()
-            .map(EthereumNodeUrl::to_string)
+            .map(|url| url.without_discovery_port().to_string())
             .collect();
         let reserved_peers = self.reserved_peers_file_path.clone();
         let ipc_path = self.ipc_path.clone();
@@ -269,9 +296,13 @@ impl ParityConfig {
         let http_jsonrpc_port = self.http_jsonrpc_port;
         let websocket_jsonrpc_port = self.websocket_jsonrpc_port;
 
-        match self.node_type {
+        let mut config = match self.node_type {
             NodeType::Miner { .. } => {
-                toml! {
+                let sealer_address = self.sealer_address.expect("miner has sealer; qed");
+                let unlock = format!("{:x?}", sealer_address);
+                let password = self.sealer_passphrase_file_path.clone();
+
+                let mut config = toml! {
                     [parity]
                     db_path = db_path
                     chain = chain
@@ -290,13 +321,6 @@ impl ParityConfig {
                     unlock = [ unlock ]
                     password = [ password ]
 
-                    [mining]
-                    author = author
-                    engine_signer = engine_signer
-                    reseal_on_txs = "none"
-                    usd_per_tx = "0"
-                    force_sealing = force_sealing
-
                     [websockets]
                     interface = "0.0.0.0"
                     port = websocket_jsonrpc_port
@@ -317,12 +341,19 @@ impl ParityConfig {
 
                     [misc]
                     logging = "network=info,miner=info,mode=info"
-                    color = true
-                }
+                    color = false
+                };
+
+                config
+                    .as_table_mut()
+                    .expect("config is a table; qed")
+                    .insert("mining".into(), self.mining_config(sealer_address));
+                config
             }
             NodeType::Transactor { .. } => {
                 toml! {
                     [parity]
+                    db_path = db_path
                     chain = chain
                     identity = identity
                     no_persistent_txqueue = false
@@ -355,18 +386,19 @@ impl ParityConfig {
 
                     [misc]
                     logging = "network=info,miner=info,mode=info"
-                    color = true
+                    color = false
                 }
             }
+        };
+
+        if let Some(ref node_key) = self.node_key {
+            config["network"]
+                .as_table_mut()
+                .expect("network config is a table; qed")
+                .insert("node_key".into(), toml::Value::String(node_key.clone()));
         }
 
         // log_file = "/var/log/parity.log"
-    }
-
-    pub fn save(&self, config_file_path: &PathBuf) -> Result<PathBuf, Error> {
-        let config = self.toml_config();
-        let data = toml::to_string(&config).expect("config is serializable; qed");
-        std::fs::File::create(config_file_path)?.write_all(data.as_bytes())?;
-        Ok(config_file_path.clone())
+        config
     }
 }
@@ -1,4 +1,5 @@
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
+extern crate libc;
 extern crate rand;
 
 #[macro_use]
@@ -8,9 +9,12 @@ extern crate error_chain;
 extern crate log;
 extern crate env_logger;
 
+extern crate clap;
+
 extern crate futures;
 extern crate tokio;
 extern crate tokio_process;
+extern crate tokio_signal;
 extern crate tokio_timer;
 
 extern crate serde;
@@ -27,29 +31,132 @@ extern crate toml;
 extern crate emerald_rs as emerald;
 extern crate ethereum_types;
 extern crate ethkey;
+extern crate hmac;
 extern crate hyper;
+extern crate hyper_tls;
 extern crate jsonrpc_core;
+extern crate native_tls;
+extern crate sha2;
+extern crate tiny_keccak;
 
 mod bootnode;
 mod ethereum;
+mod health;
 mod launcher;
+mod metrics;
+mod registry;
 mod service;
 mod types;
 
+use clap::{App, Arg, ArgMatches, SubCommand};
 use futures::Stream;
-use std::thread;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Runtime;
 
+use self::bootnode::JsonRpcClient;
 use self::bootnode::Service as BootnodeService;
+use self::bootnode::{IpcClient, PeerRefresh, RegistryClient, StaticEnodeDiscovery};
 use self::ethereum::Service as EthereumService;
+use self::health::HealthCheck;
 use self::launcher::EthereumLauncher;
-use self::service::Service;
-use self::types::Context;
+use self::metrics::Metrics;
+use self::registry::RegistryParameters;
+use self::service::{Error as ServiceError, Service};
+use self::types::{Context, EthereumNodeUrl, Passphrase, RegistryAuth};
 
 fn main() {
     env_logger::init();
 
+    let matches = App::new("ethereum-launcher")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Launcher of Parity and go-ethereum nodes")
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Initialize Ethereum client and launch it (default)"),
+        )
+        .subcommand(SubCommand::with_name("init").about("Initialize Ethereum client and exit"))
+        .subcommand(
+            SubCommand::with_name("render-config")
+                .about("Print generated chain spec and client config without writing them")
+                .arg(
+                    Arg::with_name("FILE")
+                        .possible_values(&["spec", "config"])
+                        .help("Print only the chain spec or the client config"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validators")
+                .about("Print validator addresses derived from sealer master seed"),
+        )
+        .subcommand(
+            SubCommand::with_name("enode")
+                .alias("print-enode")
+                .about("Query enode URL of the running Ethereum client"),
+        )
+        .subcommand(
+            SubCommand::with_name("bootnode-server")
+                .about("Serve bootnode registry which launchers register to and fetch static enodes from")
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .env("BOOTNODE_SERVER_PORT")
+                        .default_value("3000")
+                        .help("Port to listen on"),
+                )
+                .arg(
+                    Arg::with_name("storage")
+                        .long("storage")
+                        .env("BOOTNODE_SERVER_STORAGE")
+                        .takes_value(true)
+                        .help("JSON file to keep registered nodes across restarts"),
+                )
+                .arg(
+                    Arg::with_name("ttl")
+                        .long("ttl")
+                        .env("BOOTNODE_SERVER_TTL")
+                        .default_value("60")
+                        .help("Seconds after which nodes that stop registering expire"),
+                )
+                .arg(
+                    Arg::with_name("max-ttl")
+                        .long("max-ttl")
+                        .env("BOOTNODE_SERVER_MAX_TTL")
+                        .default_value("3600")
+                        .help("Longest lease in seconds a node may request"),
+                )
+                .arg(
+                    Arg::with_name("token")
+                        .long("token")
+                        .env("BOOTNODE_SERVER_TOKEN")
+                        .hide_env_values(true)
+                        .conflicts_with("hmac-secret")
+                        .help("Bearer token every request must carry"),
+                )
+                .arg(
+                    Arg::with_name("hmac-secret")
+                        .long("hmac-secret")
+                        .env("BOOTNODE_SERVER_HMAC_SECRET")
+                        .hide_env_values(true)
+                        .help("Shared network secret every request must be signed with"),
+                )
+                .arg(
+                    Arg::with_name("require-signature")
+                        .long("require-signature")
+                        .env("BOOTNODE_SERVER_REQUIRE_SIGNATURE")
+                        .possible_values(&["true", "false"])
+                        .default_value("false")
+                        .help("Reject registrations which are not signed with node key"),
+                ),
+        )
+        .get_matches();
+
+    // registry does not serve an Ethereum client, so it needs no context
+    if let ("bootnode-server", Some(matches)) = matches.subcommand() {
+        return bootnode_server(matches);
+    }
+
     let ctx = match Context::from_system() {
         Ok(ctx) => {
             info!("Context: {:?}", ctx);
@@ -61,89 +168,291 @@ fn main() {
         }
     };
 
-    let mut runtime = match Runtime::new() {
+    match matches.subcommand() {
+        ("init", _) => init(ctx),
+        ("render-config", Some(matches)) => render_config(ctx, matches),
+        ("validators", _) => print_validators(ctx),
+        ("enode", _) => print_enode(ctx),
+        _ => run(ctx),
+    }
+}
+
+fn bootnode_server(matches: &ArgMatches) {
+    let parse_arg = |name: &str| -> u64 {
+        let value = matches
+            .value_of(name)
+            .expect("argument has default value; qed");
+        match value.parse() {
+            Ok(value) => value,
+            Err(_) => exit_with_error(format!("invalid {}: {}", name, value)),
+        }
+    };
+
+    let port = parse_arg("port");
+    if port > u64::from(std::u16::MAX) {
+        exit_with_error(format!("invalid port: {}", port));
+    }
+    let ttl = parse_arg("ttl");
+    if ttl == 0 {
+        exit_with_error("ttl must be greater than 0");
+    }
+    let max_ttl = parse_arg("max-ttl");
+    if max_ttl < ttl {
+        exit_with_error("max-ttl must not be less than ttl");
+    }
+
+    let auth = match (matches.value_of("token"), matches.value_of("hmac-secret")) {
+        (Some(token), _) => RegistryAuth::Bearer(Passphrase::new(token.to_owned())),
+        (None, Some(secret)) => RegistryAuth::Hmac(Passphrase::new(secret.to_owned())),
+        (None, None) => RegistryAuth::None,
+    };
+
+    let parameters = RegistryParameters {
+        port: port as u16,
+        storage: matches.value_of("storage").map(PathBuf::from),
+        ttl: Duration::from_secs(ttl),
+        max_ttl: Duration::from_secs(max_ttl),
+        auth,
+        require_signature: matches.value_of("require-signature") == Some("true"),
+    };
+    let server = match registry::serve(parameters) {
+        Ok(server) => server,
+        Err(err) => exit_with_error(err),
+    };
+
+    let mut runtime = new_runtime();
+    if let Err(err) = runtime.block_on(server) {
+        exit_with_error(err);
+    }
+}
+
+fn new_runtime() -> Runtime {
+    match Runtime::new() {
         Ok(runtime) => runtime,
         Err(err) => panic!(err),
-    };
+    }
+}
 
-    // try to fetch static nodes
-    let static_nodes = match ctx.is_first_miner() {
-        true => Vec::new(),
-        false => {
-            let retry_limit = 100;
-            let mut retry_count = 0;
-            let timeout = Duration::from_secs(5);
-
-            let mut nodes = Vec::new();
-            while nodes.is_empty() && retry_count <= retry_limit {
-                info!(
-                    "Try to fetch static nodes ({}/{})",
-                    retry_count, retry_limit
-                );
-                retry_count += 1;
-                nodes = match runtime.block_on(bootnode::fetch_static_enodes(
-                    &ctx.bootnode_service_host,
-                    ctx.bootnode_service_port,
-                    &ctx.network_name,
-                )) {
-                    Ok(nodes) => nodes,
-                    Err(err) => {
-                        warn!("failed to fetch static nodes, error: {:?}", err);
-                        thread::sleep(timeout);
-                        vec![]
-                    }
-                };
-
-                if nodes.is_empty() {
-                    thread::sleep(timeout);
-                }
-            }
+fn new_launcher(ctx: &Context, bootnodes: Vec<EthereumNodeUrl>) -> EthereumLauncher {
+    EthereumLauncher {
+        chain: ctx.chain.clone(),
+        node_type: ctx.node_type.clone(),
+        engine: ctx.consensus_engine,
+        genesis_accounts: ctx.genesis_accounts.clone(),
+        sealer_passphrase: ctx.sealer_passphrase.clone(),
+        launcher_parameters: ctx.launcher_parameters,
+        bootnodes,
+        public_ip: ctx.public_ip,
+    }
+}
 
-            nodes
-        }
+fn exit_with_error<E: std::fmt::Debug>(err: E) -> ! {
+    error!("{:?}", err);
+    std::process::exit(1)
+}
+
+/// hash of rendered chain spec, which does not depend on static nodes
+fn genesis_inputs_hash(ctx: &Context) -> String {
+    match new_launcher(ctx, vec![]).genesis_inputs_hash() {
+        Ok(hash) => hash,
+        Err(err) => exit_with_error(err),
+    }
+}
+
+/// client of bootnode service which signs requests with node key, generated if not existed
+fn registry_client(ctx: &Context) -> RegistryClient {
+    let node_key = match new_launcher(ctx, vec![]).node_key() {
+        Ok(node_key) => node_key,
+        Err(err) => exit_with_error(err),
     };
+    match RegistryClient::new(&ctx.bootnode_service, Some(node_key)) {
+        Ok(client) => client,
+        Err(err) => exit_with_error(err),
+    }
+}
 
-    let (ethereum, ethereum_node_endpoint) = {
-        let launcher = EthereumLauncher {
-            node_type: ctx.node_type.clone(),
-            engine: ctx.consensus_engine,
-            launcher_parameters: ctx.launcher_parameters,
-            bootnodes: static_nodes,
-        };
+fn fetch_static_nodes(
+    ctx: &Context,
+    runtime: &mut Runtime,
+    registry_client: &RegistryClient,
+    genesis_inputs_hash: &String,
+) -> Vec<EthereumNodeUrl> {
+    // the first miner has no one to connect to
+    if ctx.is_first_miner() {
+        return Vec::new();
+    }
 
-        if ctx.first_run {
-            // initialize Ethereum
-            match launcher.initialize() {
-                Ok(_) => {}
-                Err(err) => {
-                    println!("{:?}", err);
-                    panic!(err)
-                }
-            }
+    let discovery = StaticEnodeDiscovery::new(
+        registry_client.clone(),
+        ctx.network_name.clone(),
+        genesis_inputs_hash.clone(),
+        ctx.accept_missing_genesis_inputs_hash,
+        ctx.discovery,
+    );
+    match runtime.block_on(discovery) {
+        Ok(nodes) => nodes,
+        Err(err) => exit_with_error(err),
+    }
+}
+
+fn run(ctx: Context) {
+    let mut runtime = new_runtime();
+    let metrics = Arc::new(Metrics::new());
+    let genesis_inputs_hash = genesis_inputs_hash(&ctx);
+    info!("genesis inputs hash: {}", genesis_inputs_hash);
+    let registry_client = registry_client(&ctx);
+    let static_nodes =
+        fetch_static_nodes(&ctx, &mut runtime, &registry_client, &genesis_inputs_hash);
+    metrics.set_static_enodes(static_nodes.len());
+
+    let (ethereum, ethereum_node_endpoint, ipc_path, enode_url, peers) = {
+        let launcher = new_launcher(&ctx, static_nodes.clone());
+
+        // configuration is rendered again on every start
+        if let Err(err) = launcher.initialize() {
+            exit_with_error(err);
         }
 
         let local_jsonrpc_url = launcher.local_jsonrpc_url();
+        let ipc_path = launcher.ipc_path();
+        // registered if the client does not expose its enode URL
+        let enode_url = launcher.enode_url().ok();
+        let peers = PeerRefresh::new(
+            registry_client.clone(),
+            ctx.network_name.clone(),
+            genesis_inputs_hash.clone(),
+            ctx.accept_missing_genesis_inputs_hash,
+            ctx.ethereum_program,
+            IpcClient::new(ipc_path.clone()),
+            metrics.clone(),
+            ctx.peer_refresh_interval,
+            static_nodes,
+        );
         (
-            EthereumService::new(launcher, ctx.restart_policy),
+            EthereumService::new(launcher, ctx.restart, ctx.client_log, metrics.clone()),
             local_jsonrpc_url,
+            ipc_path,
+            enode_url,
+            peers,
         )
     };
 
+    let health = ctx.health.map(|health| {
+        // only miners are expected to seal blocks
+        let max_block_age = if ctx.node_type.is_miner() {
+            Some(health.max_block_age)
+        } else {
+            None
+        };
+        let check = HealthCheck::new(
+            ethereum.running_handle(),
+            JsonRpcClient::new(&ethereum_node_endpoint),
+            metrics.clone(),
+            max_block_age,
+        );
+        match health::serve(health.port, check) {
+            Ok(server) => server,
+            Err(err) => exit_with_error(err),
+        }
+    });
+
     let bootnode = BootnodeService::new(
         ctx.network_name,
+        ctx.chain,
+        genesis_inputs_hash,
         ctx.ethereum_program,
         ctx.node_type.clone(),
-        ctx.bootnode_service_host,
-        ctx.bootnode_service_port,
-        ctx.public_ip,
+        registry_client,
+        ctx.public_host,
+        ctx.launcher_parameters.external_network_port,
         ethereum_node_endpoint,
+        IpcClient::new(ipc_path),
+        enode_url,
+        ctx.bootnode_lease,
+        metrics,
     );
 
-    let service = Service::new(ethereum, bootnode, ctx.bootnode_update_interval);
-    match runtime.block_on(service.into_future()) {
-        Ok(_) => {}
-        Err(err) => {
-            panic!(err);
+    let service = Service::new(
+        ethereum,
+        bootnode,
+        peers,
+        ctx.bootnode_update_interval,
+        ctx.shutdown_grace_period,
+        health,
+    );
+
+    // run until Ethereum client is not restarted anymore, exit with its last status
+    match runtime.block_on(service.fold(true, |_, success| Ok::<_, ServiceError>(success))) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => exit_with_error(err),
+    }
+}
+
+fn init(ctx: Context) {
+    let mut runtime = new_runtime();
+    let static_nodes = fetch_static_nodes(
+        &ctx,
+        &mut runtime,
+        &registry_client(&ctx),
+        &genesis_inputs_hash(&ctx),
+    );
+    let launcher = new_launcher(&ctx, static_nodes);
+
+    match launcher.initialize() {
+        Ok(config_file_path) => info!("Ethereum client is initialized: {}", config_file_path),
+        Err(err) => exit_with_error(err),
+    }
+}
+
+fn render_config(ctx: Context, matches: &ArgMatches) {
+    // static nodes are not fetched, bootnodes and reserved peers are rendered empty
+    let launcher = new_launcher(&ctx, vec![]);
+
+    let render_spec = || match launcher.render_spec() {
+        Ok(spec) => println!("{}", spec),
+        Err(err) => exit_with_error(err),
+    };
+    let render_config = || match launcher.render_config() {
+        Ok(config) => println!("{}", config),
+        Err(err) => exit_with_error(err),
+    };
+
+    match matches.value_of("FILE") {
+        Some("spec") => render_spec(),
+        Some("config") => render_config(),
+        _ => {
+            println!("# {}", launcher.spec_file_path().display());
+            render_spec();
+            println!("# {}", launcher.config_file_path().display());
+            render_config();
         }
     }
 }
+
+fn print_validators(ctx: Context) {
+    match ctx.node_type.validators() {
+        Ok(validators) => {
+            for validator in validators {
+                println!("0x{:x}", validator);
+            }
+        }
+        Err(err) => exit_with_error(err),
+    }
+}
+
+fn print_enode(ctx: Context) {
+    let mut runtime = new_runtime();
+    let launcher = new_launcher(&ctx, vec![]);
+    let fetcher = bootnode::fetch_enode_url(
+        &JsonRpcClient::new(&launcher.local_jsonrpc_url()),
+        &IpcClient::new(launcher.ipc_path()),
+        ctx.ethereum_program,
+        launcher.enode_url().ok(),
+    );
+
+    match runtime.block_on(fetcher) {
+        Ok(enode_url) => println!("{}", enode_url.to_string()),
+        Err(err) => exit_with_error(err),
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::Future;
+
+use super::bootnode::{Error as BootnodeError, JsonRpcClient, State as BootnodeState};
+
+/// counters and gauges of launcher services, rendered in Prometheus text format
+pub struct Metrics {
+    client_restarts: AtomicUsize,
+
+    bootnode_update_successes: AtomicUsize,
+    bootnode_update_failures: AtomicUsize,
+    /// total duration of bootnode updates, in milliseconds
+    bootnode_update_duration: AtomicUsize,
+    bootnode_state: Mutex<BootnodeState>,
+
+    static_enodes: AtomicUsize,
+}
+
+/// chain metrics queried from Ethereum client on every scrape
+pub struct ChainMetrics {
+    pub block_height: u64,
+    pub peers: u64,
+    pub pending_transactions: u64,
+    pub latest_block_timestamp: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            client_restarts: AtomicUsize::new(0),
+            bootnode_update_successes: AtomicUsize::new(0),
+            bootnode_update_failures: AtomicUsize::new(0),
+            bootnode_update_duration: AtomicUsize::new(0),
+            bootnode_state: Mutex::new(BootnodeState::Idle),
+            static_enodes: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn client_restarted(&self) {
+        self.client_restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn bootnode_updated(&self, success: bool, duration: Duration) {
+        if success {
+            self.bootnode_update_successes
+                .fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.bootnode_update_failures
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        let millis = duration.as_secs() as usize * 1000 + duration.subsec_millis() as usize;
+        self.bootnode_update_duration
+            .fetch_add(millis, Ordering::Relaxed);
+    }
+
+    pub fn set_bootnode_state(&self, state: BootnodeState) {
+        *self
+            .bootnode_state
+            .lock()
+            .expect("lock is never poisoned; qed") = state;
+    }
+
+    pub fn set_static_enodes(&self, count: usize) {
+        self.static_enodes.store(count, Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition of launcher metrics, and chain metrics if they are available
+    pub fn render(&self, chain: Option<ChainMetrics>) -> String {
+        let mut out = String::new();
+
+        counter(
+            &mut out,
+            "client_restarts_total",
+            "Restarts of Ethereum client",
+            self.client_restarts.load(Ordering::Relaxed) as u64,
+        );
+
+        let successes = self.bootnode_update_successes.load(Ordering::Relaxed) as u64;
+        let failures = self.bootnode_update_failures.load(Ordering::Relaxed) as u64;
+        header(
+            &mut out,
+            "bootnode_updates_total",
+            "Enode updates to bootnode service",
+            "counter",
+        );
+        sample(
+            &mut out,
+            "bootnode_updates_total{result=\"success\"}",
+            successes,
+        );
+        sample(
+            &mut out,
+            "bootnode_updates_total{result=\"failure\"}",
+            failures,
+        );
+
+        let duration = self.bootnode_update_duration.load(Ordering::Relaxed) as f64 / 1000.0;
+        header(
+            &mut out,
+            "bootnode_update_duration_seconds",
+            "Duration of enode updates to bootnode service",
+            "summary",
+        );
+        sample(&mut out, "bootnode_update_duration_seconds_sum", duration);
+        sample(
+            &mut out,
+            "bootnode_update_duration_seconds_count",
+            successes + failures,
+        );
+
+        let current = *self
+            .bootnode_state
+            .lock()
+            .expect("lock is never poisoned; qed");
+        header(
+            &mut out,
+            "bootnode_state",
+            "Current state of bootnode service",
+            "gauge",
+        );
+        for state in BootnodeState::all() {
+            let name = format!("bootnode_state{{state=\"{}\"}}", state.to_string());
+            sample(&mut out, name.as_str(), (state == current) as u64);
+        }
+
+        gauge(
+            &mut out,
+            "static_enodes",
+            "Static enodes fetched from bootnode service",
+            self.static_enodes.load(Ordering::Relaxed) as u64,
+        );
+
+        gauge(
+            &mut out,
+            "jsonrpc_up",
+            "Whether Ethereum client answers JSON RPC",
+            chain.is_some() as u64,
+        );
+        if let Some(chain) = chain {
+            gauge(
+                &mut out,
+                "block_height",
+                "Latest block number",
+                chain.block_height,
+            );
+            gauge(
+                &mut out,
+                "peers",
+                "Connected peers of Ethereum client",
+                chain.peers,
+            );
+            gauge(
+                &mut out,
+                "pending_transactions",
+                "Transactions in pending block",
+                chain.pending_transactions,
+            );
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs())
+                .unwrap_or(0);
+            gauge(
+                &mut out,
+                "seconds_since_last_block",
+                "Time since latest block was sealed",
+                now.saturating_sub(chain.latest_block_timestamp),
+            );
+        }
+
+        out
+    }
+}
+
+/// query chain metrics of Ethereum client
+pub fn chain_metrics(
+    client: &JsonRpcClient,
+) -> impl Future<Item = ChainMetrics, Error = BootnodeError> {
+    client
+        .eth_block_number()
+        .join4(
+            client.net_peer_count(),
+            client.pending_transaction_count(),
+            client.latest_block_timestamp(),
+        )
+        .map(
+            |(block_height, peers, pending_transactions, latest_block_timestamp)| ChainMetrics {
+                block_height,
+                peers,
+                pending_transactions,
+                latest_block_timestamp,
+            },
+        )
+}
+
+const PREFIX: &'static str = "ethereum_launcher_";
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(out, "# HELP {}{} {}", PREFIX, name, help).expect("write to string; qed");
+    writeln!(out, "# TYPE {}{} {}", PREFIX, name, kind).expect("write to string; qed");
+}
+
+fn sample<T: std::fmt::Display>(out: &mut String, name: &str, value: T) {
+    writeln!(out, "{}{} {}", PREFIX, name, value).expect("write to string; qed");
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, help, "gauge");
+    sample(out, name, value);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, help, "counter");
+    sample(out, name, value);
+}
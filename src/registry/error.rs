@@ -0,0 +1,14 @@
+error_chain! {
+    foreign_links {
+        StdIo(std::io::Error);
+        Json(serde_json::Error);
+        Hyper(hyper::Error);
+    }
+
+    errors {
+        InvalidEnodeInfo(reason: String) {
+            description("Invalid enode info")
+            display("Invalid enode info: {}", reason)
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{Future, Stream};
+use hyper::http::request::Parts;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::Value as JsonValue;
+
+use super::bootnode::{
+    request_hmac, signed_message, unix_timestamp, HMAC_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use super::types::{
+    EnodeDeregistration, EnodeInfo, EthereumNodeUrl, NodeHost, RegistryAuth, RegistryResponse,
+};
+
+mod error;
+mod store;
+
+pub use self::error::{Error, ErrorKind};
+use self::store::Store;
+
+type ResponseFuture = Box<Future<Item = Response<Body>, Error = hyper::Error> + Send>;
+
+/// authenticated and signed requests are rejected if their timestamp is further than this
+/// from server time, so that a recorded request cannot be replayed later
+const REQUEST_TIMESTAMP_WINDOW: u64 = 60;
+
+/// registry of enodes which launchers register to and fetch static enodes from
+#[derive(Debug, Clone)]
+pub struct RegistryParameters {
+    pub port: u16,
+
+    /// file to keep registrations across restarts, in memory only if not set
+    pub storage: Option<PathBuf>,
+
+    /// lease of nodes which do not request one, they expire if they do not register again
+    /// within their lease
+    pub ttl: Duration,
+
+    /// longest lease a node may request, longer requests are rejected
+    pub max_ttl: Duration,
+
+    /// bearer token or HMAC secret every request must be authenticated with
+    pub auth: RegistryAuth,
+
+    /// reject registrations and deregistrations which are not signed with node key,
+    /// signatures which are sent are verified anyway
+    pub require_signature: bool,
+}
+
+struct Registry {
+    store: Mutex<Store>,
+    auth: RegistryAuth,
+    require_signature: bool,
+}
+
+/// serve `GET /staticenodes?network=`, `POST /` with `EnodeInfo` and `DELETE /` with
+/// `EnodeDeregistration`
+pub fn serve(
+    parameters: RegistryParameters,
+) -> Result<impl Future<Item = (), Error = Error>, Error> {
+    let registry = Arc::new(Registry {
+        store: Mutex::new(Store::open(
+            parameters.storage.clone(),
+            parameters.ttl,
+            parameters.max_ttl,
+        )?),
+        auth: parameters.auth.clone(),
+        require_signature: parameters.require_signature,
+    });
+
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), parameters.port);
+    let server = Server::try_bind(&addr)?.serve(move || {
+        let registry = registry.clone();
+        service_fn(move |req| handle(&registry, req))
+    });
+
+    info!(target: "registry", "serve bootnode registry on {}, storage: {:?}, TTL: {:?}, max TTL: {:?}, auth: {:?}, require signature: {}",
+          addr, parameters.storage, parameters.ttl, parameters.max_ttl, parameters.auth, parameters.require_signature);
+    Ok(server.from_err())
+}
+
+fn handle(registry: &Arc<Registry>, req: Request<Body>) -> ResponseFuture {
+    let registry = registry.clone();
+    let (parts, body) = req.into_parts();
+    Box::new(
+        body.concat2()
+            .map(move |data| registry.respond(&parts, &data)),
+    )
+}
+
+impl Registry {
+    fn respond(&self, parts: &Parts, body: &[u8]) -> Response<Body> {
+        if let Err(reason) = self.authenticate(parts, body) {
+            warn!(target: "registry", "reject {} {}: {}", parts.method, parts.uri, reason);
+            return rejected(StatusCode::UNAUTHORIZED, reason);
+        }
+
+        match (&parts.method, parts.uri.path()) {
+            (&Method::GET, "/staticenodes") => {
+                let network = parts.uri.query().and_then(|query| {
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .find(|(key, _)| key == "network")
+                        .map(|(_, value)| value.into_owned())
+                });
+                match network {
+                    Some(network) => {
+                        let mut store = self.store.lock().expect("lock is never poisoned; qed");
+                        match store.static_enodes(network.as_str()) {
+                            Ok(enodes) => response(StatusCode::OK, JsonValue::Array(enodes)),
+                            Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+                        }
+                    }
+                    None => rejected(StatusCode::BAD_REQUEST, "missing network"),
+                }
+            }
+            (&Method::POST, "/") => {
+                let info: EnodeInfo = match serde_json::from_slice(body) {
+                    Ok(info) => info,
+                    Err(err) => return error_response(StatusCode::BAD_REQUEST, err),
+                };
+                if let Err(reason) = self.verify_signature(parts, body, &info.enode) {
+                    warn!(target: "registry", "reject registration of {}: {}", info.enode, reason);
+                    return rejected(StatusCode::UNAUTHORIZED, reason);
+                }
+
+                let mut store = self.store.lock().expect("lock is never poisoned; qed");
+                match store.register(info) {
+                    Ok(ttl) => accepted(Some(ttl)),
+                    Err(Error(ErrorKind::InvalidEnodeInfo(reason), _)) => {
+                        rejected(StatusCode::BAD_REQUEST, reason)
+                    }
+                    Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+                }
+            }
+            (&Method::DELETE, "/") => {
+                let node: EnodeDeregistration = match serde_json::from_slice(body) {
+                    Ok(node) => node,
+                    Err(err) => return error_response(StatusCode::BAD_REQUEST, err),
+                };
+                if let Err(reason) = self.verify_signature(parts, body, &node.enode) {
+                    warn!(target: "registry", "reject deregistration of {}: {}", node.enode, reason);
+                    return rejected(StatusCode::UNAUTHORIZED, reason);
+                }
+
+                let mut store = self.store.lock().expect("lock is never poisoned; qed");
+                match store.deregister(node) {
+                    Ok(_) => accepted(None),
+                    Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+                }
+            }
+            _ => rejected(StatusCode::NOT_FOUND, "not found"),
+        }
+    }
+
+    /// check bearer token or HMAC of request
+    fn authenticate(&self, parts: &Parts, body: &[u8]) -> Result<(), String> {
+        let (header, expected) = match self.auth {
+            RegistryAuth::None => return Ok(()),
+            RegistryAuth::Bearer(ref token) => {
+                ("Authorization", format!("Bearer {}", token.as_str()))
+            }
+            RegistryAuth::Hmac(ref secret) => {
+                let timestamp = fresh_timestamp(parts)?;
+                let path_and_query = parts
+                    .uri
+                    .path_and_query()
+                    .map(|path_and_query| path_and_query.as_str())
+                    .unwrap_or("/");
+                (
+                    HMAC_HEADER,
+                    request_hmac(
+                        secret.as_str(),
+                        &parts.method,
+                        path_and_query,
+                        timestamp,
+                        body,
+                    ),
+                )
+            }
+        };
+
+        match parts.headers.get(header) {
+            Some(value) if constant_time_eq(value.as_bytes(), expected.as_bytes()) => Ok(()),
+            Some(_) => Err(format!("invalid {}", header)),
+            None => Err(format!("missing {}", header)),
+        }
+    }
+
+    /// check that request body is signed with the key of node `node_id`
+    fn verify_signature(&self, parts: &Parts, body: &[u8], node_id: &str) -> Result<(), String> {
+        let signature = match parts.headers.get(SIGNATURE_HEADER) {
+            Some(signature) => signature
+                .to_str()
+                .ok()
+                .and_then(|signature| ethkey::Signature::from_str(signature).ok())
+                .ok_or_else(|| format!("invalid {}", SIGNATURE_HEADER))?,
+            None if self.require_signature => {
+                return Err(format!("missing {}", SIGNATURE_HEADER));
+            }
+            None => return Ok(()),
+        };
+
+        let timestamp = fresh_timestamp(parts)?;
+        let public = ethkey::recover(&signature, &signed_message(timestamp, body))
+            .map_err(|err| format!("invalid {}: {}", SIGNATURE_HEADER, err))?;
+        if format!("{:x}", public) != node_id.to_lowercase() {
+            return Err("signature does not match enode".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// timestamp of request if it is within `REQUEST_TIMESTAMP_WINDOW` of server time
+fn fresh_timestamp(parts: &Parts) -> Result<&str, String> {
+    let timestamp = parts
+        .headers
+        .get(TIMESTAMP_HEADER)
+        .ok_or_else(|| format!("missing {}", TIMESTAMP_HEADER))?
+        .to_str()
+        .map_err(|_| format!("invalid {}", TIMESTAMP_HEADER))?;
+    let sent = timestamp
+        .parse::<u64>()
+        .map_err(|_| format!("invalid {}", TIMESTAMP_HEADER))?;
+
+    let now = unix_timestamp();
+    if sent.max(now) - sent.min(now) > REQUEST_TIMESTAMP_WINDOW {
+        return Err(format!(
+            "{} {} is not within {} seconds of server time {}",
+            TIMESTAMP_HEADER, sent, REQUEST_TIMESTAMP_WINDOW, now
+        ));
+    }
+    Ok(timestamp)
+}
+
+/// compare secrets without leaking where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn accepted(ttl: Option<u64>) -> Response<Body> {
+    let body = RegistryResponse {
+        ok: true,
+        error: None,
+        ttl,
+    };
+    response(StatusCode::OK, json!(body))
+}
+
+fn rejected<S: Into<String>>(status: StatusCode, reason: S) -> Response<Body> {
+    let body = RegistryResponse {
+        ok: false,
+        error: Some(reason.into()),
+        ttl: None,
+    };
+    response(status, json!(body))
+}
+
+fn error_response<E: std::fmt::Display>(status: StatusCode, err: E) -> Response<Body> {
+    warn!(target: "registry", "{}", err);
+    rejected(status, err.to_string())
+}
+
+fn response(status: StatusCode, body: JsonValue) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("response builder; qed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(timestamp: Option<String>) -> Parts {
+        let mut builder = Request::builder();
+        builder.method(Method::DELETE).uri("/");
+        if let Some(timestamp) = timestamp {
+            builder.header(TIMESTAMP_HEADER, timestamp.as_str());
+        }
+        builder
+            .body(())
+            .expect("request builder; qed")
+            .into_parts()
+            .0
+    }
+
+    #[test]
+    fn accept_fresh_timestamp() {
+        let now = unix_timestamp();
+        assert!(fresh_timestamp(&parts(Some(now.to_string()))).is_ok());
+        assert!(fresh_timestamp(&parts(Some((now - 30).to_string()))).is_ok());
+        assert!(fresh_timestamp(&parts(Some((now + 30).to_string()))).is_ok());
+    }
+
+    #[test]
+    fn reject_stale_or_missing_timestamp() {
+        let now = unix_timestamp();
+        assert!(fresh_timestamp(&parts(Some((now - 120).to_string()))).is_err());
+        assert!(fresh_timestamp(&parts(Some((now + 120).to_string()))).is_err());
+        assert!(fresh_timestamp(&parts(Some("yesterday".to_owned()))).is_err());
+        assert!(fresh_timestamp(&parts(None)).is_err());
+    }
+
+    #[test]
+    fn hmac_covers_timestamp() {
+        let body = br#"{"enode":"6f8a","network":"test"}"#;
+        let mac = request_hmac("secret", &Method::DELETE, "/", "1500000000", body);
+        assert_eq!(
+            mac,
+            request_hmac("secret", &Method::DELETE, "/", "1500000000", body)
+        );
+        assert_ne!(
+            mac,
+            request_hmac("secret", &Method::DELETE, "/", "1500000060", body)
+        );
+        assert_ne!(
+            signed_message("1500000000", body),
+            signed_message("1500000060", body)
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value as JsonValue;
+
+use super::{EnodeDeregistration, EnodeInfo, EthereumNodeUrl, NodeHost};
+use super::{Error, ErrorKind};
+
+/// latest registration of a node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Registration {
+    info: EnodeInfo,
+
+    /// seconds since UNIX epoch
+    last_seen: u64,
+
+    /// lease in seconds
+    ttl: u64,
+}
+
+impl Registration {
+    /// public IP or host name of node container, or address reported by Ethereum client
+    /// if it is not known
+    fn enode_url(&self) -> Option<EthereumNodeUrl> {
+        let public_host = self.info.public_ip.parse::<NodeHost>().ok();
+        let host = match public_host {
+            Some(ref host) if !host.is_unspecified() => host.clone(),
+            _ => self.info.ip.parse().ok()?,
+        };
+
+        Some(EthereumNodeUrl {
+            node_id: self.info.enode.clone(),
+            host,
+            port: self.info.port,
+            discovery_port: self.info.discovery_port,
+        })
+    }
+}
+
+/// registered nodes of every network, kept in memory and optionally in a JSON file
+pub struct Store {
+    /// node ID to registration, per network
+    networks: BTreeMap<String, BTreeMap<String, Registration>>,
+    path: Option<PathBuf>,
+
+    /// lease of nodes which do not request one
+    ttl: Duration,
+
+    /// longest lease a node may request
+    max_ttl: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0)
+}
+
+impl Store {
+    /// load registrations of previous run from `path` if it exists
+    pub fn open(path: Option<PathBuf>, ttl: Duration, max_ttl: Duration) -> Result<Store, Error> {
+        let networks = match path {
+            Some(ref path) if path.exists() => {
+                let data = std::fs::read_to_string(path)?;
+                serde_json::from_str(data.as_str())?
+            }
+            _ => BTreeMap::new(),
+        };
+
+        let mut store = Store {
+            networks,
+            path,
+            ttl,
+            max_ttl,
+        };
+        store.expire()?;
+        Ok(store)
+    }
+
+    /// register a node or renew its registration, returns granted lease in seconds
+    pub fn register(&mut self, info: EnodeInfo) -> Result<u64, Error> {
+        if info.network.is_empty() {
+            return Err(Error::from(ErrorKind::InvalidEnodeInfo(
+                "network is empty".to_owned(),
+            )));
+        }
+        if info.enode.is_empty() || !info.enode.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::from(ErrorKind::InvalidEnodeInfo(format!(
+                "invalid node ID {:?}",
+                info.enode
+            ))));
+        }
+
+        let ttl = info.ttl.unwrap_or(self.ttl.as_secs());
+        if ttl == 0 {
+            return Err(Error::from(ErrorKind::InvalidEnodeInfo(
+                "ttl is 0".to_owned(),
+            )));
+        }
+        if ttl > self.max_ttl.as_secs() {
+            return Err(Error::from(ErrorKind::InvalidEnodeInfo(format!(
+                "ttl {} exceeds maximum {}",
+                ttl,
+                self.max_ttl.as_secs()
+            ))));
+        }
+
+        let registration = Registration {
+            info,
+            last_seen: now(),
+            ttl,
+        };
+        if registration.enode_url().is_none() {
+            return Err(Error::from(ErrorKind::InvalidEnodeInfo(format!(
+                "invalid address {:?} and public address {:?}",
+                registration.info.ip, registration.info.public_ip
+            ))));
+        }
+
+        let nodes = self
+            .networks
+            .entry(registration.info.network.clone())
+            .or_insert_with(BTreeMap::new);
+        if !nodes.contains_key(&registration.info.enode) {
+            info!(target: "registry", "register node {} of network {}",
+                  registration.info.enode, registration.info.network);
+        }
+        nodes.insert(registration.info.enode.clone(), registration);
+
+        self.expire()?;
+        self.persist()?;
+        Ok(ttl)
+    }
+
+    /// remove registration of a node which leaves, unknown nodes are ignored
+    pub fn deregister(&mut self, node: EnodeDeregistration) -> Result<(), Error> {
+        let removed = match self.networks.get_mut(&node.network) {
+            Some(nodes) => nodes.remove(&node.enode).is_some(),
+            None => false,
+        };
+        if !removed {
+            return Ok(());
+        }
+
+        info!(target: "registry", "deregister node {} of network {}", node.enode, node.network);
+        self.networks.retain(|_, nodes| !nodes.is_empty());
+        self.persist()
+    }
+
+    /// static enodes of live nodes in `network`, with their genesis inputs hash if known
+    pub fn static_enodes(&mut self, network: &str) -> Result<Vec<JsonValue>, Error> {
+        self.expire()?;
+
+        let nodes = match self.networks.get(network) {
+            Some(nodes) => nodes,
+            None => return Ok(vec![]),
+        };
+        Ok(nodes
+            .values()
+            .filter_map(|registration| {
+                let enode_url = registration.enode_url()?.to_string();
+                Some(match registration.info.genesis_inputs_hash {
+                    Some(ref hash) => json!({ "enode": enode_url, "genesisInputsHash": hash }),
+                    None => json!(enode_url),
+                })
+            })
+            .collect())
+    }
+
+    /// drop nodes which have not heart-beaten within their lease, and networks without nodes
+    fn expire(&mut self) -> Result<(), Error> {
+        let now = now();
+
+        let mut expired = false;
+        for (network, nodes) in self.networks.iter_mut() {
+            nodes.retain(|node_id, registration| {
+                let alive = registration.last_seen.saturating_add(registration.ttl) >= now;
+                if !alive {
+                    info!(target: "registry", "node {} of network {} expired", node_id, network);
+                    expired = true;
+                }
+                alive
+            });
+        }
+        self.networks.retain(|_, nodes| !nodes.is_empty());
+
+        if expired {
+            self.persist()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// write registrations into a temporary file and rename it, so that the file is never partial
+    fn persist(&self) -> Result<(), Error> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        std::fs::File::create(&tmp_path)?
+            .write_all(serde_json::to_string(&self.networks)?.as_bytes())?;
+        std::fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODE_ID: &str = "6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0";
+
+    fn store() -> Store {
+        Store::open(None, Duration::from_secs(60), Duration::from_secs(600)).unwrap()
+    }
+
+    fn enode_info(ttl: Option<u64>) -> EnodeInfo {
+        EnodeInfo {
+            enode: NODE_ID.to_owned(),
+            port: 30303,
+            ip: "10.3.58.6".to_owned(),
+            public_ip: "node0.example.com".to_owned(),
+            discovery_port: None,
+            network: "test".to_owned(),
+            network_id: 17,
+            chain_id: 17,
+            genesis_inputs_hash: None,
+            miner: true,
+            ttl,
+        }
+    }
+
+    fn registration(store: &mut Store) -> &mut Registration {
+        store
+            .networks
+            .get_mut("test")
+            .and_then(|nodes| nodes.get_mut(NODE_ID))
+            .expect("node is registered; qed")
+    }
+
+    #[test]
+    fn register_and_deregister() {
+        let mut store = store();
+        assert_eq!(store.register(enode_info(None)).unwrap(), 60);
+        assert_eq!(store.register(enode_info(Some(300))).unwrap(), 300);
+        assert_eq!(
+            store.static_enodes("test").unwrap(),
+            vec![json!(format!(
+                "enode://{}@node0.example.com:30303",
+                NODE_ID
+            ))]
+        );
+
+        store
+            .deregister(EnodeDeregistration {
+                enode: NODE_ID.to_owned(),
+                network: "test".to_owned(),
+            })
+            .unwrap();
+        assert!(store.static_enodes("test").unwrap().is_empty());
+        assert!(store.networks.is_empty());
+    }
+
+    #[test]
+    fn reject_ttl_out_of_bounds() {
+        let mut store = store();
+        assert!(store.register(enode_info(Some(0))).is_err());
+        assert!(store.register(enode_info(Some(601))).is_err());
+        assert!(store.register(enode_info(Some(u64::max_value()))).is_err());
+        assert_eq!(store.register(enode_info(Some(600))).unwrap(), 600);
+    }
+
+    #[test]
+    fn expire_nodes_after_their_lease() {
+        let mut store = store();
+        store.register(enode_info(Some(30))).unwrap();
+
+        registration(&mut store).last_seen = now() - 29;
+        assert_eq!(store.static_enodes("test").unwrap().len(), 1);
+
+        registration(&mut store).last_seen = now() - 31;
+        assert!(store.static_enodes("test").unwrap().is_empty());
+        assert!(store.networks.is_empty());
+    }
+
+    #[test]
+    fn expire_does_not_overflow() {
+        let mut store = store();
+        store.register(enode_info(None)).unwrap();
+
+        registration(&mut store).ttl = u64::max_value();
+        assert_eq!(store.static_enodes("test").unwrap().len(), 1);
+    }
+}
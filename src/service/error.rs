@@ -3,6 +3,7 @@ error_chain! {
         EthereumError(super::EthereumError);
         BootnodeServiceError(super::BootnodeServiceError);
         TimerError(tokio_timer::Error);
+        StdIoError(std::io::Error);
     }
 
     errors {
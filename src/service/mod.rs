@@ -1,27 +1,45 @@
-use futures::{Async, Poll, Stream};
+use futures::{Async, Future, Poll, Stream};
 use std::time::Duration;
+use tokio_signal::unix::{Signal, SIGINT, SIGTERM};
 use tokio_timer::Interval;
 
-use super::bootnode::{Error as BootnodeServiceError, Service as BootnodeService};
+use super::bootnode::{Error as BootnodeServiceError, PeerRefresh, Service as BootnodeService};
 use super::ethereum::{Error as EthereumError, Service as EthereumService};
+use super::health::HealthServer;
 
 mod error;
 
 pub use self::error::{Error, ErrorKind};
 
+type ShutdownSignals = Box<Stream<Item = i32, Error = std::io::Error> + Send>;
+
 pub struct Service {
     ethereum: EthereumService,
     bootnode: BootnodeService,
+    peers: PeerRefresh,
     ticker: Interval,
+    health: Option<HealthServer>,
+
+    shutdown_signals: ShutdownSignals,
+    shutdown_grace_period: Duration,
+    stopping: bool,
 }
 
 impl Service {
     pub fn new(
         ethereum: EthereumService,
         bootnode: BootnodeService,
+        peers: PeerRefresh,
         bootnode_update_interval: Duration,
+        shutdown_grace_period: Duration,
+        health: Option<HealthServer>,
     ) -> Service {
         let ticker = Interval::new_interval(bootnode_update_interval);
+        let shutdown_signals = Box::new(
+            Signal::new(SIGTERM)
+                .flatten_stream()
+                .select(Signal::new(SIGINT).flatten_stream()),
+        );
 
         // force update
         let mut bootnode = bootnode;
@@ -30,27 +48,89 @@ impl Service {
         Service {
             ethereum,
             bootnode,
+            peers,
             ticker,
+            health,
+
+            shutdown_signals,
+            shutdown_grace_period,
+            stopping: false,
+        }
+    }
+
+    /// deregister from bootnode service and wait for Ethereum client to exit
+    fn shutdown(&mut self, signal: i32) {
+        if self.stopping {
+            warn!(target: "service", "received signal {} again, kill Ethereum client", signal);
+            self.ethereum.kill();
+            return;
+        }
+
+        info!(target: "service", "received signal {}, shutting down", signal);
+        self.stopping = true;
+        self.bootnode.stop();
+        self.ethereum.stop(self.shutdown_grace_period);
+    }
+
+    /// health server keeps serving while shutting down, it is dropped if it fails
+    fn poll_health(&mut self) {
+        let failed = match self.health {
+            Some(ref mut health) => match health.poll() {
+                Ok(Async::NotReady) => false,
+                Ok(Async::Ready(_)) => true,
+                Err(err) => {
+                    warn!(target: "service", "health server failed: {}", err);
+                    true
+                }
+            },
+            None => false,
+        };
+
+        if failed {
+            self.health = None;
         }
     }
 }
 
+/// yields success of every exit of Ethereum client, ends when it is not restarted anymore
 impl Stream for Service {
-    type Item = ();
+    type Item = bool;
     type Error = Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
         loop {
-            match self.ethereum.poll() {
-                Ok(Async::Ready(_)) => {
-                    return Ok(Async::Ready(Some(())));
+            while let Async::Ready(Some(signal)) = self.shutdown_signals.poll()? {
+                self.shutdown(signal);
+            }
+            self.poll_health();
+
+            let ethereum_finished = match self.ethereum.poll() {
+                Ok(Async::Ready(Some(exit))) => {
+                    return Ok(Async::Ready(Some(exit)));
                 }
-                Ok(Async::NotReady) => {}
+                Ok(Async::Ready(None)) => true,
+                Ok(Async::NotReady) => false,
                 Err(err) => return Err(Error::from(err)),
+            };
+
+            // bootnode service finishes deregistration while stopping
+            let bootnode_finished = match self.bootnode.poll() {
+                Ok(Async::Ready(None)) => true,
+                Ok(_) => false,
+                Err(err) => return Err(Error::from(err)),
+            };
+
+            if ethereum_finished && (bootnode_finished || !self.stopping) {
+                return Ok(Async::Ready(None));
+            }
+            if self.stopping {
+                return Ok(Async::NotReady);
             }
 
-            if let Err(err) = self.bootnode.poll() {
-                return Err(Error::from(err));
+            while let Async::Ready(Some(peers)) = self.peers.poll()? {
+                if let Err(err) = self.ethereum.update_peers(peers) {
+                    warn!(target: "service", "failed to persist peers: {}", err);
+                }
             }
 
             match self.ticker.poll() {
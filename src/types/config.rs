@@ -0,0 +1,704 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use super::{DiscoveryFailurePolicy, GenesisAccount, Passphrase, Quantity, RestartPolicy};
+use super::{Error, ErrorKind};
+
+/// launcher configuration loaded from `LAUNCHER_CONFIG` file,
+/// every key can be overridden by its environment variable
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LauncherConfig {
+    /// `NETWORK_NAME`
+    pub network_name: Option<String>,
+
+    /// `PUBLIC_IP`
+    pub public_ip: Option<IpAddr>,
+
+    /// `PUBLIC_HOSTNAME`, advertised in enode URL instead of public IP
+    pub public_hostname: Option<String>,
+
+    /// `RESTART_POLICY`
+    pub restart_policy: Option<RestartPolicy>,
+
+    #[serde(default)]
+    pub restart: RestartConfig,
+
+    /// `SHUTDOWN_GRACE_PERIOD`, in seconds, to wait for Ethereum client before SIGKILL
+    pub shutdown_grace_period: Option<u64>,
+
+    #[serde(default)]
+    pub client_log: ClientLogConfig,
+
+    /// `GENESIS_BLOCK_GAS_LIMIT`
+    pub genesis_block_gas_limit: Option<Quantity>,
+
+    /// `GENESIS_ALLOC`, as JSON array
+    #[serde(default)]
+    pub alloc: Vec<GenesisAccount>,
+
+    #[serde(default)]
+    pub prefund: PrefundConfig,
+
+    #[serde(default)]
+    pub chain: ChainConfig,
+
+    #[serde(default)]
+    pub forks: ForksConfig,
+
+    #[serde(default)]
+    pub node: NodeConfig,
+
+    #[serde(default)]
+    pub consensus: ConsensusConfig,
+
+    #[serde(default)]
+    pub ports: PortsConfig,
+
+    #[serde(default)]
+    pub bootnode_service: BootnodeServiceConfig,
+
+    #[serde(default)]
+    pub health: HealthConfig,
+}
+
+/// backoff and crash-loop detection of Ethereum client restarts
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestartConfig {
+    /// `RESTART_INITIAL_BACKOFF`, in milliseconds
+    pub initial_backoff: Option<u64>,
+
+    /// `RESTART_MAX_BACKOFF`, in milliseconds
+    pub max_backoff: Option<u64>,
+
+    /// `RESTART_MAX_COUNT`, restarts allowed within `window`
+    pub max_count: Option<usize>,
+
+    /// `RESTART_WINDOW`, in seconds
+    pub window: Option<u64>,
+}
+
+/// Ethereum client output is always logged, and optionally written into
+/// a size-rotated file under config directory
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientLogConfig {
+    /// `CLIENT_LOG_FILE`
+    pub file: Option<bool>,
+
+    /// `CLIENT_LOG_MAX_SIZE`, in bytes
+    pub max_size: Option<u64>,
+
+    /// `CLIENT_LOG_MAX_FILES`, rotated files to keep
+    pub max_files: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    /// `CHAIN_NAME`
+    pub name: Option<String>,
+
+    /// `NETWORK_ID`
+    pub network_id: Option<Quantity>,
+
+    /// `CHAIN_ID`
+    pub chain_id: Option<Quantity>,
+}
+
+/// hard forks activated at genesis and transition blocks of later ones
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForksConfig {
+    /// `HARD_FORK`, every fork up to this one is activated at genesis
+    pub level: Option<String>,
+
+    /// `HARD_FORK_BLOCKS`, as JSON object of fork name and activation block
+    #[serde(default)]
+    pub blocks: BTreeMap<String, u64>,
+
+    /// `EIP_TRANSITIONS`, as JSON object of EIP name and transition block, Parity only
+    #[serde(default)]
+    pub eip_transitions: BTreeMap<String, u64>,
+}
+
+/// accounts derived from sealer master seed and funded in genesis block
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrefundConfig {
+    /// `PREFUND_ACCOUNT_COUNT`
+    pub count: Option<usize>,
+
+    /// `PREFUND_ACCOUNT_BALANCE`
+    pub balance: Option<Quantity>,
+
+    /// `PREFUND_HD_PATH`, index of account is appended
+    pub hd_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeConfig {
+    /// `NODE_TYPE`
+    #[serde(rename = "type")]
+    pub node_type: Option<String>,
+
+    /// `SEALER_MASTER_SEED`
+    pub sealer_master_seed: Option<String>,
+
+    /// `SEALER_PASSPHRASE`, passphrase of sealer key file
+    pub sealer_passphrase: Option<Passphrase>,
+
+    /// `SEALER_PASSPHRASE_FILE`, used if `sealer_passphrase` is not set,
+    /// a random passphrase is generated if neither is set
+    pub sealer_passphrase_file: Option<String>,
+
+    /// `MINER_INDEX`
+    pub miner_index: Option<usize>,
+
+    /// `MINER_COUNT`
+    pub miner_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConsensusConfig {
+    /// `CONSENSUS_ENGINE`
+    pub engine: Option<String>,
+
+    /// `ETHASH_CONSENSUS_PARAMETERS`, `AURA_CONSENSUS_PARAMETERS` or `CLIQUE_CONSENSUS_PARAMETERS`
+    pub parameters: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortsConfig {
+    /// `P2P_NETWORK_SERVICE_PORT`
+    pub p2p_network: Option<u16>,
+
+    /// `P2P_NETWORK_EXTERNAL_PORT`, P2P port mapped to `p2p_network` on public IP
+    pub p2p_network_external: Option<u16>,
+
+    /// `HTTP_JSON_RPC_PORT`
+    pub http_jsonrpc: Option<u16>,
+
+    /// `WEBSOCKET_JSON_RPC_PORT`
+    pub websocket_jsonrpc: Option<u16>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BootnodeServiceConfig {
+    /// `BOOTNODE_SERVICE_HOST`
+    pub host: Option<String>,
+
+    /// `BOOTNODE_SERVICE_PORT`
+    pub port: Option<u16>,
+
+    /// `BOOTNODE_SERVICE_UPDATE_INTERVAL`, in seconds
+    pub update_interval: Option<u64>,
+
+    /// `BOOTNODE_SERVICE_LEASE`, in seconds, after which the registration of this node expires
+    /// unless it is renewed
+    pub lease: Option<u64>,
+
+    /// `PEER_REFRESH_INTERVAL`, in seconds, to fetch static enodes again while running
+    pub peer_refresh_interval: Option<u64>,
+
+    /// `BOOTNODE_SERVICE_TLS`, connect with HTTPS
+    pub tls: Option<bool>,
+
+    /// `BOOTNODE_SERVICE_CA_FILE`, PEM bundle of additional trusted CA certificates
+    pub ca_file: Option<String>,
+
+    /// `BOOTNODE_SERVICE_TOKEN`, bearer token sent with every request
+    pub token: Option<Passphrase>,
+
+    /// `BOOTNODE_SERVICE_TOKEN_FILE`, used if `token` is not set
+    pub token_file: Option<String>,
+
+    /// `BOOTNODE_SERVICE_HMAC_SECRET`, shared network secret to sign every request with
+    pub hmac_secret: Option<Passphrase>,
+
+    /// `BOOTNODE_SERVICE_HMAC_SECRET_FILE`, used if `hmac_secret` is not set
+    pub hmac_secret_file: Option<String>,
+
+    /// `BOOTNODE_SERVICE_ACCEPT_MISSING_GENESIS_INPUTS_HASH`, accept static enodes whose genesis
+    /// is unknown, for bootnode services which do not record genesis inputs hashes
+    pub accept_missing_genesis_inputs_hash: Option<bool>,
+
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+}
+
+/// retry strategy of fetching static enodes before Ethereum client is started
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    /// `DISCOVERY_MIN_PEERS`, static enodes to wait for
+    pub min_peers: Option<usize>,
+
+    /// `DISCOVERY_DEADLINE`, in seconds
+    pub deadline: Option<u64>,
+
+    /// `DISCOVERY_INITIAL_BACKOFF`, in milliseconds
+    pub initial_backoff: Option<u64>,
+
+    /// `DISCOVERY_MAX_BACKOFF`, in milliseconds
+    pub max_backoff: Option<u64>,
+
+    /// `DISCOVERY_ON_FAILURE`, `fail` or `continue` with the enodes fetched so far
+    pub on_failure: Option<DiscoveryFailurePolicy>,
+}
+
+/// HTTP server of `/healthz`, `/readyz` and `/metrics`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthConfig {
+    /// `HEALTH_PORT`, the server is disabled if not set
+    pub port: Option<u16>,
+
+    /// `HEALTH_MAX_BLOCK_PERIODS`, a miner is not ready if its latest block
+    /// is older than this many block periods
+    pub max_block_periods: Option<u32>,
+}
+
+impl LauncherConfig {
+    pub fn from_system() -> Result<LauncherConfig, Error> {
+        let mut config = match env::var("LAUNCHER_CONFIG") {
+            Ok(path) => {
+                info!(target: "context", "load launcher config from {}", path);
+                LauncherConfig::from_file(&path)?
+            }
+            Err(_) => LauncherConfig::default(),
+        };
+
+        config.override_from_env(|key| env::var(key).ok())?;
+        Ok(config)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<LauncherConfig, Error> {
+        let data = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(data.as_str())?)
+    }
+
+    /// override values with environment variables, `var` looks them up
+    fn override_from_env<F: Fn(&str) -> Option<String>>(&mut self, var: F) -> Result<(), Error> {
+        let env = EnvOverrides(var);
+        env.value("NETWORK_NAME", &mut self.network_name)?;
+        env.value("PUBLIC_IP", &mut self.public_ip)?;
+        env.value("PUBLIC_HOSTNAME", &mut self.public_hostname)?;
+        env.value("RESTART_POLICY", &mut self.restart_policy)?;
+        env.value("SHUTDOWN_GRACE_PERIOD", &mut self.shutdown_grace_period)?;
+        env.value("CLIENT_LOG_FILE", &mut self.client_log.file)?;
+        env.value("CLIENT_LOG_MAX_SIZE", &mut self.client_log.max_size)?;
+        env.value("CLIENT_LOG_MAX_FILES", &mut self.client_log.max_files)?;
+        env.value("RESTART_INITIAL_BACKOFF", &mut self.restart.initial_backoff)?;
+        env.value("RESTART_MAX_BACKOFF", &mut self.restart.max_backoff)?;
+        env.value("RESTART_MAX_COUNT", &mut self.restart.max_count)?;
+        env.value("RESTART_WINDOW", &mut self.restart.window)?;
+        env.value("GENESIS_BLOCK_GAS_LIMIT", &mut self.genesis_block_gas_limit)?;
+
+        env.json("GENESIS_ALLOC", &mut self.alloc)?;
+        env.value("PREFUND_ACCOUNT_COUNT", &mut self.prefund.count)?;
+        env.value("PREFUND_ACCOUNT_BALANCE", &mut self.prefund.balance)?;
+        env.value("PREFUND_HD_PATH", &mut self.prefund.hd_path)?;
+
+        env.value("CHAIN_NAME", &mut self.chain.name)?;
+        env.value("NETWORK_ID", &mut self.chain.network_id)?;
+        env.value("CHAIN_ID", &mut self.chain.chain_id)?;
+
+        env.value("HARD_FORK", &mut self.forks.level)?;
+        env.json("HARD_FORK_BLOCKS", &mut self.forks.blocks)?;
+        env.json("EIP_TRANSITIONS", &mut self.forks.eip_transitions)?;
+
+        env.value("NODE_TYPE", &mut self.node.node_type)?;
+        env.value("SEALER_MASTER_SEED", &mut self.node.sealer_master_seed)?;
+        env.value("SEALER_PASSPHRASE", &mut self.node.sealer_passphrase)?;
+        env.value(
+            "SEALER_PASSPHRASE_FILE",
+            &mut self.node.sealer_passphrase_file,
+        )?;
+        env.value("MINER_INDEX", &mut self.node.miner_index)?;
+        env.value("MINER_COUNT", &mut self.node.miner_count)?;
+
+        env.value("CONSENSUS_ENGINE", &mut self.consensus.engine)?;
+        if let Some(ref engine) = self.consensus.engine {
+            let key = format!("{}_CONSENSUS_PARAMETERS", engine.to_uppercase());
+            env.value(key.as_str(), &mut self.consensus.parameters)?;
+        }
+
+        env.value("P2P_NETWORK_SERVICE_PORT", &mut self.ports.p2p_network)?;
+        env.value(
+            "P2P_NETWORK_EXTERNAL_PORT",
+            &mut self.ports.p2p_network_external,
+        )?;
+        env.value("HTTP_JSON_RPC_PORT", &mut self.ports.http_jsonrpc)?;
+        env.value("WEBSOCKET_JSON_RPC_PORT", &mut self.ports.websocket_jsonrpc)?;
+
+        env.value("BOOTNODE_SERVICE_HOST", &mut self.bootnode_service.host)?;
+        env.value("BOOTNODE_SERVICE_PORT", &mut self.bootnode_service.port)?;
+        env.value(
+            "BOOTNODE_SERVICE_UPDATE_INTERVAL",
+            &mut self.bootnode_service.update_interval,
+        )?;
+        env.value("BOOTNODE_SERVICE_LEASE", &mut self.bootnode_service.lease)?;
+        env.value(
+            "PEER_REFRESH_INTERVAL",
+            &mut self.bootnode_service.peer_refresh_interval,
+        )?;
+        env.value("BOOTNODE_SERVICE_TLS", &mut self.bootnode_service.tls)?;
+        env.value(
+            "BOOTNODE_SERVICE_CA_FILE",
+            &mut self.bootnode_service.ca_file,
+        )?;
+        env.value("BOOTNODE_SERVICE_TOKEN", &mut self.bootnode_service.token)?;
+        env.value(
+            "BOOTNODE_SERVICE_TOKEN_FILE",
+            &mut self.bootnode_service.token_file,
+        )?;
+        env.value(
+            "BOOTNODE_SERVICE_HMAC_SECRET",
+            &mut self.bootnode_service.hmac_secret,
+        )?;
+        env.value(
+            "BOOTNODE_SERVICE_HMAC_SECRET_FILE",
+            &mut self.bootnode_service.hmac_secret_file,
+        )?;
+        env.value(
+            "BOOTNODE_SERVICE_ACCEPT_MISSING_GENESIS_INPUTS_HASH",
+            &mut self.bootnode_service.accept_missing_genesis_inputs_hash,
+        )?;
+        env.value(
+            "DISCOVERY_MIN_PEERS",
+            &mut self.bootnode_service.discovery.min_peers,
+        )?;
+        env.value(
+            "DISCOVERY_DEADLINE",
+            &mut self.bootnode_service.discovery.deadline,
+        )?;
+        env.value(
+            "DISCOVERY_INITIAL_BACKOFF",
+            &mut self.bootnode_service.discovery.initial_backoff,
+        )?;
+        env.value(
+            "DISCOVERY_MAX_BACKOFF",
+            &mut self.bootnode_service.discovery.max_backoff,
+        )?;
+        env.value(
+            "DISCOVERY_ON_FAILURE",
+            &mut self.bootnode_service.discovery.on_failure,
+        )?;
+
+        env.value("HEALTH_PORT", &mut self.health.port)?;
+        env.value(
+            "HEALTH_MAX_BLOCK_PERIODS",
+            &mut self.health.max_block_periods,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// environment variables looked up by a function, so that each configuration value is named
+/// once and parse errors name the variable
+struct EnvOverrides<F>(F);
+
+impl<F: Fn(&str) -> Option<String>> EnvOverrides<F> {
+    /// parse variable `key` into `field` if it is set
+    fn value<T>(&self, key: &str, field: &mut Option<T>) -> Result<(), Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = (self.0)(key) {
+            *field = Some(value.parse().map_err(|err| invalid_env(key, &value, err))?);
+        }
+        Ok(())
+    }
+
+    /// parse JSON variable `key` into `field` if it is set
+    fn json<T: DeserializeOwned>(&self, key: &str, field: &mut T) -> Result<(), Error> {
+        if let Some(value) = (self.0)(key) {
+            *field = serde_json::from_str(value.as_str())
+                .map_err(|err| invalid_env(key, &value, err))?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid_env<E: fmt::Display>(key: &str, value: &str, err: E) -> Error {
+    Error::from(ErrorKind::InvalidConfigValue(
+        key.to_owned(),
+        format!("{} ({})", value, err),
+    ))
+}
+
+/// convert a quantity configuration value into `u64`
+pub fn quantity_to_u64(value: Quantity, key: &str) -> Result<u64, Error> {
+    value.to_u64().ok_or_else(|| {
+        Error::from(ErrorKind::InvalidConfigValue(
+            key.to_owned(),
+            value.0.to_string(),
+        ))
+    })
+}
+
+/// unwrap a required configuration value
+pub fn required<T>(value: Option<T>, key: &str) -> Result<T, Error> {
+    value.ok_or_else(|| Error::from(ErrorKind::MissingConfigValue(key.to_owned())))
+}
+
+/// secret given as value, or read from file without trailing newline
+pub fn secret(
+    value: Option<Passphrase>,
+    file: Option<String>,
+    file_key: &str,
+) -> Result<Option<Passphrase>, Error> {
+    match (value, file) {
+        (Some(value), _) => Ok(Some(value)),
+        (None, Some(path)) => {
+            let data = std::fs::read_to_string(path.as_str())?;
+            let value = data.trim_end_matches(|c| c == '\r' || c == '\n');
+            if value.is_empty() {
+                return Err(Error::from(ErrorKind::InvalidConfigValue(
+                    file_key.to_owned(),
+                    path,
+                )));
+            }
+            Ok(Some(Passphrase::new(value.to_owned())))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// every environment variable of the README configuration example, with a valid value
+    const OVERRIDES: &[(&str, &str)] = &[
+        ("NETWORK_NAME", "testnet"),
+        ("PUBLIC_IP", "10.0.0.4"),
+        ("PUBLIC_HOSTNAME", "miner-0.example.com"),
+        ("RESTART_POLICY", "on-failure"),
+        ("SHUTDOWN_GRACE_PERIOD", "30"),
+        ("GENESIS_BLOCK_GAS_LIMIT", "8000000"),
+        ("CLIENT_LOG_FILE", "true"),
+        ("CLIENT_LOG_MAX_SIZE", "1024"),
+        ("CLIENT_LOG_MAX_FILES", "3"),
+        ("RESTART_INITIAL_BACKOFF", "500"),
+        ("RESTART_MAX_BACKOFF", "5000"),
+        ("RESTART_MAX_COUNT", "7"),
+        ("RESTART_WINDOW", "60"),
+        ("CHAIN_NAME", "test-chain"),
+        ("NETWORK_ID", "0x2323"),
+        ("CHAIN_ID", "17"),
+        ("PREFUND_ACCOUNT_COUNT", "10"),
+        ("PREFUND_ACCOUNT_BALANCE", "1000"),
+        ("PREFUND_HD_PATH", "m/44'/60'/1'/0"),
+        (
+            "GENESIS_ALLOC",
+            r#"[{"address": "0x00a329c0648769a73afac7f9381e08fb43dbea72", "balance": "0x1"}]"#,
+        ),
+        ("HARD_FORK", "petersburg"),
+        ("HARD_FORK_BLOCKS", r#"{"istanbul": 100}"#),
+        ("EIP_TRANSITIONS", r#"{"eip1283": 0}"#),
+        ("NODE_TYPE", "miner"),
+        ("SEALER_MASTER_SEED", "rose rocket"),
+        ("SEALER_PASSPHRASE_FILE", "/run/secrets/sealer-passphrase"),
+        ("SEALER_PASSPHRASE", "secret"),
+        ("MINER_INDEX", "1"),
+        ("MINER_COUNT", "2"),
+        ("CONSENSUS_ENGINE", "aura"),
+        ("P2P_NETWORK_SERVICE_PORT", "30304"),
+        ("P2P_NETWORK_EXTERNAL_PORT", "30305"),
+        ("HTTP_JSON_RPC_PORT", "8547"),
+        ("WEBSOCKET_JSON_RPC_PORT", "8548"),
+        ("BOOTNODE_SERVICE_HOST", "bootnode.example.com"),
+        ("BOOTNODE_SERVICE_PORT", "3000"),
+        ("BOOTNODE_SERVICE_UPDATE_INTERVAL", "10"),
+        ("BOOTNODE_SERVICE_LEASE", "30"),
+        ("PEER_REFRESH_INTERVAL", "60"),
+        ("BOOTNODE_SERVICE_TLS", "true"),
+        ("BOOTNODE_SERVICE_CA_FILE", "/etc/bootnode/ca.pem"),
+        ("BOOTNODE_SERVICE_TOKEN", "token"),
+        ("BOOTNODE_SERVICE_TOKEN_FILE", "/run/secrets/token"),
+        ("BOOTNODE_SERVICE_HMAC_SECRET", "hmac"),
+        ("BOOTNODE_SERVICE_HMAC_SECRET_FILE", "/run/secrets/hmac"),
+        (
+            "BOOTNODE_SERVICE_ACCEPT_MISSING_GENESIS_INPUTS_HASH",
+            "true",
+        ),
+        ("DISCOVERY_MIN_PEERS", "2"),
+        ("DISCOVERY_DEADLINE", "300"),
+        ("DISCOVERY_INITIAL_BACKOFF", "1000"),
+        ("DISCOVERY_MAX_BACKOFF", "30000"),
+        ("DISCOVERY_ON_FAILURE", "continue"),
+        ("HEALTH_PORT", "8080"),
+        ("HEALTH_MAX_BLOCK_PERIODS", "5"),
+    ];
+
+    /// configuration value which environment variable `key` is expected to set
+    fn field(config: &LauncherConfig, key: &str) -> String {
+        let secret =
+            |value: &Option<Passphrase>| format!("{:?}", value.as_ref().map(|s| s.as_str()));
+        match key {
+            "NETWORK_NAME" => format!("{:?}", config.network_name),
+            "PUBLIC_IP" => format!("{:?}", config.public_ip),
+            "PUBLIC_HOSTNAME" => format!("{:?}", config.public_hostname),
+            "RESTART_POLICY" => format!("{:?}", config.restart_policy),
+            "SHUTDOWN_GRACE_PERIOD" => format!("{:?}", config.shutdown_grace_period),
+            "GENESIS_BLOCK_GAS_LIMIT" => format!("{:?}", config.genesis_block_gas_limit),
+            "CLIENT_LOG_FILE" => format!("{:?}", config.client_log.file),
+            "CLIENT_LOG_MAX_SIZE" => format!("{:?}", config.client_log.max_size),
+            "CLIENT_LOG_MAX_FILES" => format!("{:?}", config.client_log.max_files),
+            "RESTART_INITIAL_BACKOFF" => format!("{:?}", config.restart.initial_backoff),
+            "RESTART_MAX_BACKOFF" => format!("{:?}", config.restart.max_backoff),
+            "RESTART_MAX_COUNT" => format!("{:?}", config.restart.max_count),
+            "RESTART_WINDOW" => format!("{:?}", config.restart.window),
+            "CHAIN_NAME" => format!("{:?}", config.chain.name),
+            "NETWORK_ID" => format!("{:?}", config.chain.network_id),
+            "CHAIN_ID" => format!("{:?}", config.chain.chain_id),
+            "PREFUND_ACCOUNT_COUNT" => format!("{:?}", config.prefund.count),
+            "PREFUND_ACCOUNT_BALANCE" => format!("{:?}", config.prefund.balance),
+            "PREFUND_HD_PATH" => format!("{:?}", config.prefund.hd_path),
+            "GENESIS_ALLOC" => format!("{:?}", config.alloc),
+            "HARD_FORK" => format!("{:?}", config.forks.level),
+            "HARD_FORK_BLOCKS" => format!("{:?}", config.forks.blocks),
+            "EIP_TRANSITIONS" => format!("{:?}", config.forks.eip_transitions),
+            "NODE_TYPE" => format!("{:?}", config.node.node_type),
+            "SEALER_MASTER_SEED" => format!("{:?}", config.node.sealer_master_seed),
+            "SEALER_PASSPHRASE_FILE" => format!("{:?}", config.node.sealer_passphrase_file),
+            "SEALER_PASSPHRASE" => secret(&config.node.sealer_passphrase),
+            "MINER_INDEX" => format!("{:?}", config.node.miner_index),
+            "MINER_COUNT" => format!("{:?}", config.node.miner_count),
+            "CONSENSUS_ENGINE" => format!("{:?}", config.consensus.engine),
+            "P2P_NETWORK_SERVICE_PORT" => format!("{:?}", config.ports.p2p_network),
+            "P2P_NETWORK_EXTERNAL_PORT" => format!("{:?}", config.ports.p2p_network_external),
+            "HTTP_JSON_RPC_PORT" => format!("{:?}", config.ports.http_jsonrpc),
+            "WEBSOCKET_JSON_RPC_PORT" => format!("{:?}", config.ports.websocket_jsonrpc),
+            "BOOTNODE_SERVICE_HOST" => format!("{:?}", config.bootnode_service.host),
+            "BOOTNODE_SERVICE_PORT" => format!("{:?}", config.bootnode_service.port),
+            "BOOTNODE_SERVICE_UPDATE_INTERVAL" => {
+                format!("{:?}", config.bootnode_service.update_interval)
+            }
+            "BOOTNODE_SERVICE_LEASE" => format!("{:?}", config.bootnode_service.lease),
+            "PEER_REFRESH_INTERVAL" => {
+                format!("{:?}", config.bootnode_service.peer_refresh_interval)
+            }
+            "BOOTNODE_SERVICE_TLS" => format!("{:?}", config.bootnode_service.tls),
+            "BOOTNODE_SERVICE_CA_FILE" => format!("{:?}", config.bootnode_service.ca_file),
+            "BOOTNODE_SERVICE_TOKEN" => secret(&config.bootnode_service.token),
+            "BOOTNODE_SERVICE_TOKEN_FILE" => format!("{:?}", config.bootnode_service.token_file),
+            "BOOTNODE_SERVICE_HMAC_SECRET" => secret(&config.bootnode_service.hmac_secret),
+            "BOOTNODE_SERVICE_HMAC_SECRET_FILE" => {
+                format!("{:?}", config.bootnode_service.hmac_secret_file)
+            }
+            "BOOTNODE_SERVICE_ACCEPT_MISSING_GENESIS_INPUTS_HASH" => format!(
+                "{:?}",
+                config.bootnode_service.accept_missing_genesis_inputs_hash
+            ),
+            "DISCOVERY_MIN_PEERS" => format!("{:?}", config.bootnode_service.discovery.min_peers),
+            "DISCOVERY_DEADLINE" => format!("{:?}", config.bootnode_service.discovery.deadline),
+            "DISCOVERY_INITIAL_BACKOFF" => {
+                format!("{:?}", config.bootnode_service.discovery.initial_backoff)
+            }
+            "DISCOVERY_MAX_BACKOFF" => {
+                format!("{:?}", config.bootnode_service.discovery.max_backoff)
+            }
+            "DISCOVERY_ON_FAILURE" => {
+                format!("{:?}", config.bootnode_service.discovery.on_failure)
+            }
+            "HEALTH_PORT" => format!("{:?}", config.health.port),
+            "HEALTH_MAX_BLOCK_PERIODS" => format!("{:?}", config.health.max_block_periods),
+            _ => panic!("no configuration value for {}", key),
+        }
+    }
+
+    /// environment variables noted in the configuration example of README
+    fn documented_variables() -> Vec<String> {
+        let readme = include_str!("../../README.md");
+        let example = readme
+            .split("```toml")
+            .nth(1)
+            .and_then(|rest| rest.split("```").next())
+            .expect("README has a TOML configuration example; qed");
+
+        let mut variables: Vec<String> = example
+            .split(|c: char| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+            .filter(|word| word.contains('_') && word.starts_with(|c: char| c.is_ascii_uppercase()))
+            .map(str::to_owned)
+            .collect();
+        variables.sort();
+        variables.dedup();
+        variables
+    }
+
+    fn from_env(vars: &[(&str, &str)]) -> Result<LauncherConfig, Error> {
+        let mut config = LauncherConfig::default();
+        config.override_from_env(|key| {
+            vars.iter()
+                .find(|(var, _)| *var == key)
+                .map(|(_, value)| value.to_string())
+        })?;
+        Ok(config)
+    }
+
+    #[test]
+    fn every_documented_variable_is_overridden() {
+        let mut keys: Vec<String> = OVERRIDES.iter().map(|(key, _)| key.to_string()).collect();
+        keys.sort();
+        assert_eq!(documented_variables(), keys);
+    }
+
+    #[test]
+    fn each_variable_sets_its_own_value() {
+        let default = LauncherConfig::default();
+        for &(key, value) in OVERRIDES {
+            let config = from_env(&[(key, value)]).unwrap();
+            for &(other, _) in OVERRIDES {
+                let changed = field(&config, other) != field(&default, other);
+                assert_eq!(changed, other == key, "{}={} sets {}", key, value, other);
+            }
+        }
+    }
+
+    #[test]
+    fn consensus_parameters_follow_engine() {
+        let parameters = r#"{"blockPeriod": 5}"#;
+        let config = from_env(&[("AURA_CONSENSUS_PARAMETERS", parameters)]).unwrap();
+        assert!(config.consensus.parameters.is_none());
+
+        let config = from_env(&[
+            ("CONSENSUS_ENGINE", "aura"),
+            ("AURA_CONSENSUS_PARAMETERS", parameters),
+        ])
+        .unwrap();
+        assert_eq!(config.consensus.parameters, Some(json!({"blockPeriod": 5})));
+    }
+
+    #[test]
+    fn invalid_value_names_variable() {
+        match from_env(&[("HTTP_JSON_RPC_PORT", "80800")]) {
+            Err(Error(ErrorKind::InvalidConfigValue(key, _), _)) => {
+                assert_eq!(key, "HTTP_JSON_RPC_PORT")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match from_env(&[("HARD_FORK_BLOCKS", "[1]")]) {
+            Err(Error(ErrorKind::InvalidConfigValue(key, _), _)) => {
+                assert_eq!(key, "HARD_FORK_BLOCKS")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
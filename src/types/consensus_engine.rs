@@ -1,11 +1,21 @@
+use std::time::Duration;
+
 use ethereum_types::U256;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
 
+use super::quantity::{self, Quantity};
 use super::EthereumProgram;
+use super::{Error, ErrorKind};
+
+/// target block time of Ethash in seconds, `durationLimit` of Parity chain spec
+pub const ETHASH_DURATION_LIMIT: u64 = 13;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ConsensusEngine {
     Ethash {
         genesis_difficulty: U256,
+        block_reward: U256,
     },
     ParityAura {
         block_period: u64,
@@ -16,7 +26,133 @@ pub enum ConsensusEngine {
     },
 }
 
+/// parameters of `ETHASH_CONSENSUS_PARAMETERS`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct EthashParameters {
+    pub genesis_block_difficulty: Quantity,
+    pub block_reward: Quantity,
+}
+
+impl Default for EthashParameters {
+    fn default() -> EthashParameters {
+        EthashParameters {
+            genesis_block_difficulty: Quantity::from(16384),
+            block_reward: Quantity(U256::from(3) * U256::from(10).pow(18.into())),
+        }
+    }
+}
+
+/// parameters of `AURA_CONSENSUS_PARAMETERS`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct AuraParameters {
+    #[serde(deserialize_with = "quantity::deserialize_u64")]
+    pub block_period: u64,
+    pub block_reward: Quantity,
+}
+
+impl Default for AuraParameters {
+    fn default() -> AuraParameters {
+        AuraParameters {
+            block_period: 7,
+            block_reward: Quantity(U256::from(5) * U256::from(10).pow(18.into())),
+        }
+    }
+}
+
+/// parameters of `CLIQUE_CONSENSUS_PARAMETERS`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct CliqueParameters {
+    #[serde(deserialize_with = "quantity::deserialize_u64")]
+    pub block_period: u64,
+}
+
+impl Default for CliqueParameters {
+    fn default() -> CliqueParameters {
+        CliqueParameters { block_period: 7 }
+    }
+}
+
+/// deserialize engine parameters, report the first bad field if there is any
+fn parse_parameters<T: DeserializeOwned>(engine: &str, parameters: JsonValue) -> Result<T, Error> {
+    let fields = match parameters {
+        JsonValue::Object(ref fields) => fields,
+        _ => {
+            return Err(Error::from(ErrorKind::InvalidConsensusParameters(
+                engine.to_owned(),
+                parameters.to_string(),
+            )))
+        }
+    };
+
+    // every field has a default value, so each field can be checked alone
+    for (field, value) in fields {
+        let mut single = serde_json::Map::new();
+        single.insert(field.clone(), value.clone());
+        if let Err(err) = serde_json::from_value::<T>(JsonValue::Object(single)) {
+            return Err(Error::from(ErrorKind::InvalidConsensusParameter(
+                engine.to_owned(),
+                field.clone(),
+                err.to_string(),
+            )));
+        }
+    }
+
+    Ok(serde_json::from_value(parameters)?)
+}
+
+fn check_non_zero(engine: &str, field: &str, value: U256) -> Result<(), Error> {
+    if value.is_zero() {
+        return Err(Error::from(ErrorKind::InvalidConsensusParameter(
+            engine.to_owned(),
+            field.to_owned(),
+            "must be greater than zero".to_owned(),
+        )));
+    }
+    Ok(())
+}
+
 impl ConsensusEngine {
+    pub fn from_parameters(engine: &str, parameters: JsonValue) -> Result<ConsensusEngine, Error> {
+        match engine.to_lowercase().as_ref() {
+            "ethash" => {
+                let params: EthashParameters = parse_parameters(engine, parameters)?;
+                check_non_zero(
+                    engine,
+                    "genesisBlockDifficulty",
+                    params.genesis_block_difficulty.0,
+                )?;
+
+                Ok(ConsensusEngine::Ethash {
+                    genesis_difficulty: params.genesis_block_difficulty.0,
+                    block_reward: params.block_reward.0,
+                })
+            }
+            "aura" => {
+                let params: AuraParameters = parse_parameters(engine, parameters)?;
+                check_non_zero(engine, "blockPeriod", params.block_period.into())?;
+
+                Ok(ConsensusEngine::ParityAura {
+                    block_period: params.block_period,
+                    block_reward: params.block_reward.0,
+                })
+            }
+            "clique" => {
+                let params: CliqueParameters = parse_parameters(engine, parameters)?;
+                check_non_zero(engine, "blockPeriod", params.block_period.into())?;
+
+                Ok(ConsensusEngine::GethClique {
+                    block_period: params.block_period,
+                })
+            }
+            _ => Err(Error::from(ErrorKind::InvalidConsensusEngineType(
+                engine.to_owned(),
+            ))),
+        }
+    }
+
     pub fn program(&self) -> EthereumProgram {
         match self {
             ConsensusEngine::Ethash { .. } => EthereumProgram::Parity,
@@ -24,4 +160,83 @@ impl ConsensusEngine {
             ConsensusEngine::GethClique { .. } => EthereumProgram::GoEthereum,
         }
     }
+
+    /// expected time between two blocks
+    pub fn block_period(&self) -> Duration {
+        match self {
+            ConsensusEngine::Ethash { .. } => Duration::from_secs(ETHASH_DURATION_LIMIT),
+            ConsensusEngine::ParityAura { block_period, .. } => Duration::from_secs(*block_period),
+            ConsensusEngine::GethClique { block_period } => Duration::from_secs(*block_period),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected_field(engine: &str, parameters: &str) -> Option<String> {
+        let parameters = serde_json::from_str(parameters).unwrap();
+        match ConsensusEngine::from_parameters(engine, parameters) {
+            Err(Error(ErrorKind::InvalidConsensusParameter(_, field, _), _)) => Some(field),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn missing_parameters_use_defaults() {
+        match ConsensusEngine::from_parameters("clique", json!({})).unwrap() {
+            ConsensusEngine::GethClique { block_period } => assert_eq!(block_period, 7),
+            other => panic!("unexpected engine: {:?}", other),
+        }
+
+        match ConsensusEngine::from_parameters("aura", json!({"blockPeriod": "0x3"})).unwrap() {
+            ConsensusEngine::ParityAura { block_period, .. } => assert_eq!(block_period, 3),
+            other => panic!("unexpected engine: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_parameter_is_rejected() {
+        assert_eq!(
+            rejected_field("clique", r#"{"blockPeriod": 5, "blockReward": 1}"#),
+            Some("blockReward".to_owned())
+        );
+        assert_eq!(
+            rejected_field("ethash", r#"{"blockperiod": 5}"#),
+            Some("blockperiod".to_owned())
+        );
+    }
+
+    #[test]
+    fn malformed_parameter_is_rejected() {
+        assert_eq!(
+            rejected_field("aura", r#"{"blockPeriod": "seven"}"#),
+            Some("blockPeriod".to_owned())
+        );
+        assert_eq!(
+            rejected_field("ethash", r#"{"blockReward": -1}"#),
+            Some("blockReward".to_owned())
+        );
+    }
+
+    #[test]
+    fn zero_parameter_is_rejected() {
+        assert_eq!(
+            rejected_field("clique", r#"{"blockPeriod": 0}"#),
+            Some("blockPeriod".to_owned())
+        );
+        assert_eq!(
+            rejected_field("ethash", r#"{"genesisBlockDifficulty": "0x0"}"#),
+            Some("genesisBlockDifficulty".to_owned())
+        );
+    }
+
+    #[test]
+    fn non_object_parameters_are_rejected() {
+        match ConsensusEngine::from_parameters("aura", json!([7])) {
+            Err(Error(ErrorKind::InvalidConsensusParameters(..), _)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
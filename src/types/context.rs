@@ -1,89 +1,183 @@
-use std::net::IpAddr;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
 use std::time::Duration;
 
-use ethereum_types::{self, U256};
+use ethereum_types::U256;
 
-use super::{ConsensusEngine, EthereumProgram, LauncherParameters, NodeType, RestartPolicy};
-use super::{Error, ErrorKind};
+use super::config::{quantity_to_u64, required, secret};
+use super::{
+    BootnodeServiceParameters, ChainParameters, ClientLogParameters, ConsensusEngine,
+    DiscoveryFailurePolicy, DiscoveryParameters, EthereumProgram, ForkSchedule, GenesisAccount,
+    HardFork, HealthParameters, LauncherParameters, NodeHost, NodeType, Passphrase, Quantity,
+    RegistryAuth, RestartParameters, RestartPolicy,
+};
+use super::{Error, ErrorKind, LauncherConfig};
+
+const DEFAULT_NETWORK_ID: u64 = 0x2323;
+const DEFAULT_HARD_FORK: HardFork = HardFork::Byzantium;
+const DEFAULT_PREFUND_HD_PATH: &'static str = "m/44'/60'/1'/0";
+
+// restart backoff in milliseconds, restart window in seconds
+const DEFAULT_INITIAL_BACKOFF: u64 = 1000;
+const DEFAULT_MAX_BACKOFF: u64 = 60_000;
+const DEFAULT_MAX_RESTARTS: usize = 5;
+const DEFAULT_RESTART_WINDOW: u64 = 600;
+const DEFAULT_SHUTDOWN_GRACE_PERIOD: u64 = 30;
+
+// 10 MiB
+const DEFAULT_CLIENT_LOG_MAX_SIZE: u64 = 10 * 1024 * 1024;
+const DEFAULT_CLIENT_LOG_MAX_FILES: usize = 5;
+
+const DEFAULT_HEALTH_MAX_BLOCK_PERIODS: u32 = 5;
+
+// discovery backoff in milliseconds, deadline in seconds
+const DEFAULT_DISCOVERY_MIN_PEERS: usize = 1;
+const DEFAULT_DISCOVERY_DEADLINE: u64 = 300;
+const DEFAULT_DISCOVERY_INITIAL_BACKOFF: u64 = 1000;
+const DEFAULT_DISCOVERY_MAX_BACKOFF: u64 = 30_000;
+const DEFAULT_PEER_REFRESH_INTERVAL: u64 = 60;
+
+// registration survives this many missed updates by default
+const DEFAULT_BOOTNODE_LEASE_INTERVALS: u64 = 3;
 
 #[derive(Debug, Clone)]
 pub struct Context {
-    /// is this the first time we run Ethereum service
-    pub first_run: bool,
-
     /// public IP address of this container
     pub public_ip: IpAddr,
 
+    /// host advertised in enode URL, public host name or public IP
+    pub public_host: NodeHost,
+
     /// name of this ethereum network
     pub network_name: String,
 
+    /// chain name, network ID and chain ID of this ethereum network
+    pub chain: ChainParameters,
+
     /// consensus engine type and its parameters
     pub consensus_engine: ConsensusEngine,
 
+    /// pre-funded accounts in genesis block
+    pub genesis_accounts: Vec<GenesisAccount>,
+
     /// which Ethereum program should we execute
     pub ethereum_program: EthereumProgram,
 
     /// node type of this container
     pub node_type: NodeType,
 
-    /// restart policy
-    pub restart_policy: RestartPolicy,
+    /// passphrase of sealer key file, randomly generated if not given
+    pub sealer_passphrase: Option<Passphrase>,
+
+    /// restart policy, backoff and crash-loop limit
+    pub restart: RestartParameters,
+
+    /// time to wait for Ethereum client to exit after SIGTERM
+    pub shutdown_grace_period: Duration,
+
+    /// log file of Ethereum client output
+    pub client_log: ClientLogParameters,
 
     /// common launcher parameters
     pub launcher_parameters: LauncherParameters,
 
-    /// hostname of bootnode service
-    pub bootnode_service_host: String,
-
-    /// port of bootnode service
-    pub bootnode_service_port: u16,
+    /// address, TLS and authentication of bootnode service
+    pub bootnode_service: BootnodeServiceParameters,
 
     /// interval for update enode URL to bootnode service
     pub bootnode_update_interval: Duration,
-}
 
-fn is_first_run() -> Result<bool, Error> {
-    use std::env;
-    use std::path::PathBuf;
-
-    let home_dir = env::var("HOME")?;
-    std::fs::create_dir_all(home_dir.clone()).expect("this should always successful");
-    let lock_path = {
-        let mut path = PathBuf::new();
-        path.push(home_dir);
-        path.push("first-run-lock");
-        path
-    };
-
-    match std::fs::File::open(lock_path.clone()) {
-        Ok(_) => {
-            info!(target: "context", "first-run lock is existed, this is not first run");
-            Ok(false)
-        }
-        Err(_err) => {
-            info!(target: "context",
-                "first-run lock is not existed, this is first run, try to create first-run lock: {:?}",
-                lock_path);
-
-            // create first-run lock
-            std::fs::File::create(lock_path)?;
-            Ok(true)
-        }
-    }
+    /// registration of this node expires if it is not renewed within lease
+    pub bootnode_lease: Duration,
+
+    /// retry strategy of fetching static enodes from bootnode service
+    pub discovery: DiscoveryParameters,
+
+    /// interval for fetching static enodes while Ethereum client is running
+    pub peer_refresh_interval: Duration,
+
+    /// accept static enodes without genesis inputs hash, they are rejected otherwise
+    pub accept_missing_genesis_inputs_hash: bool,
+
+    /// health, readiness and metrics endpoints, disabled if not set
+    pub health: Option<HealthParameters>,
 }
 
 impl Context {
     pub fn from_system() -> Result<Context, Error> {
-        use std::env;
-        let network_name = env::var("NETWORK_NAME")?;
+        Context::from_config(LauncherConfig::from_system()?)
+    }
+
+    pub fn from_config(config: LauncherConfig) -> Result<Context, Error> {
+        let network_name = required(config.network_name, "network_name (NETWORK_NAME)")?;
+
+        let consensus_engine = {
+            let engine = required(
+                config.consensus.engine,
+                "consensus.engine (CONSENSUS_ENGINE)",
+            )?;
+            let engine_parameters = config.consensus.parameters.unwrap_or(json!({}));
+            ConsensusEngine::from_parameters(engine.as_str(), engine_parameters)?
+        };
+
+        let chain = {
+            let network_id = match config.chain.network_id {
+                Some(network_id) => quantity_to_u64(network_id, "chain.network_id (NETWORK_ID)")?,
+                None => DEFAULT_NETWORK_ID,
+            };
+            let chain_id = match config.chain.chain_id {
+                Some(chain_id) => quantity_to_u64(chain_id, "chain.chain_id (CHAIN_ID)")?,
+                None => network_id,
+            };
+
+            let forks = {
+                let level = match config.forks.level {
+                    Some(level) => level.parse()?,
+                    None => DEFAULT_HARD_FORK,
+                };
+                let mut blocks = BTreeMap::new();
+                for (fork, block) in config.forks.blocks {
+                    blocks.insert(fork.parse()?, block);
+                }
+
+                // go-ethereum only supports transitions of whole hard forks
+                let eip_transitions = config.forks.eip_transitions;
+                if let (EthereumProgram::GoEthereum, false) =
+                    (consensus_engine.program(), eip_transitions.is_empty())
+                {
+                    return Err(Error::from(ErrorKind::UnsupportedEipTransitions(
+                        "go-ethereum".to_owned(),
+                    )));
+                }
+
+                ForkSchedule::new(level, blocks, eip_transitions)?
+            };
+
+            ChainParameters {
+                name: config.chain.name.unwrap_or(network_name.clone()),
+                network_id,
+                chain_id,
+                forks,
+            }
+        };
+
+        let sealer_passphrase = secret(
+            config.node.sealer_passphrase.clone(),
+            config.node.sealer_passphrase_file.clone(),
+            "node.sealer_passphrase_file (SEALER_PASSPHRASE_FILE)",
+        )?;
 
         let node_type = {
-            let node_type = env::var("NODE_TYPE")?;
+            let node = config.node;
+            let node_type = required(node.node_type, "node.type (NODE_TYPE)")?;
 
             match node_type.to_lowercase().as_ref() {
                 "transactor" => {
-                    let seed = env::var("SEALER_MASTER_SEED")?;
-                    let count: usize = env::var("MINER_COUNT")?.parse()?;
+                    let seed = required(
+                        node.sealer_master_seed,
+                        "node.sealer_master_seed (SEALER_MASTER_SEED)",
+                    )?;
+                    let count = required(node.miner_count, "node.miner_count (MINER_COUNT)")?;
 
                     NodeType::Transactor {
                         sealer_mnemonic: seed,
@@ -91,9 +185,12 @@ impl Context {
                     }
                 }
                 "miner" => {
-                    let seed = env::var("SEALER_MASTER_SEED")?;
-                    let index: usize = env::var("MINER_INDEX")?.parse()?;
-                    let miner_count: usize = env::var("MINER_COUNT")?.parse()?;
+                    let seed = required(
+                        node.sealer_master_seed,
+                        "node.sealer_master_seed (SEALER_MASTER_SEED)",
+                    )?;
+                    let index = required(node.miner_index, "node.miner_index (MINER_INDEX)")?;
+                    let miner_count = required(node.miner_count, "node.miner_count (MINER_COUNT)")?;
 
                     if index >= miner_count {
                         return Err(Error::from(ErrorKind::TooLargeMinerIndex(
@@ -112,86 +209,249 @@ impl Context {
             }
         };
 
-        let consensus_engine = {
-            use serde_json::Value as JsonValue;
-
-            let engine = env::var("CONSENSUS_ENGINE")?;
-            match engine.to_lowercase().as_ref() {
-                "ethash" => {
-                    let engine_parameters: JsonValue =
-                        serde_json::from_str(env::var("ETHASH_CONSENSUS_PARAMETERS")?.as_str())?;
-                    let genesis_difficulty: U256 = engine_parameters["genesisBlockDifficulty"]
-                        .as_u64()
-                        .unwrap_or(16384)
-                        .into();
-                    ConsensusEngine::Ethash { genesis_difficulty }
-                }
-                "aura" => {
-                    let engine_parameters: JsonValue =
-                        serde_json::from_str(env::var("AURA_CONSENSUS_PARAMETERS")?.as_str())?;
-                    let block_period = engine_parameters["blockPeriod"].as_u64().unwrap_or(7);
-
-                    let block_reward = U256::from_dec_str(
-                        engine_parameters["blockReward"]
-                            .as_str()
-                            .unwrap_or("5000000000000000000".into()),
-                    )
-                    .unwrap_or(U256::from(5) * U256::from(10).pow(18.into()));
-
-                    ConsensusEngine::ParityAura {
-                        block_period,
-                        block_reward,
-                    }
-                }
-                "clique" => {
-                    let engine_parameters: JsonValue =
-                        serde_json::from_str(env::var("CLIQUE_CONSENSUS_PARAMETERS")?.as_str())?;
-                    let block_period = engine_parameters["blockPeriod"].as_u64().unwrap_or(7);
-                    ConsensusEngine::GethClique { block_period }
-                }
-                _ => {
-                    return Err(Error::from(ErrorKind::InvalidConsensusEngineType(engine)));
+        let genesis_accounts = {
+            let mut accounts = config.alloc;
+
+            let prefund = config.prefund;
+            let count = prefund.count.unwrap_or(0);
+            if count > 0 {
+                // 1,000,000 ether
+                let balance = prefund
+                    .balance
+                    .unwrap_or(Quantity(U256::from(10).pow(24.into())));
+                let hd_path = prefund
+                    .hd_path
+                    .unwrap_or(DEFAULT_PREFUND_HD_PATH.to_owned());
+                for address in node_type.derived_accounts(hd_path.as_str(), count)? {
+                    accounts.push(GenesisAccount::new(address, balance));
                 }
             }
+
+            accounts
         };
 
+        let ports = config.ports;
+        let network_port = required(
+            ports.p2p_network,
+            "ports.p2p_network (P2P_NETWORK_SERVICE_PORT)",
+        )?;
         let launcher_parameters = LauncherParameters {
-            network_port: env::var("P2P_NETWORK_SERVICE_PORT")?.parse()?,
-            http_jsonrpc_port: env::var("HTTP_JSON_RPC_PORT")?.parse()?,
-            websocket_jsonrpc_port: env::var("WEBSOCKET_JSON_RPC_PORT")?.parse()?,
-            genesis_block_gas_limit: {
-                use std::str::FromStr;
-                let raw_value = env::var("GENESIS_BLOCK_GAS_LIMIT")?;
-                match U256::from_str(ethereum_types::clean_0x(raw_value.as_str())) {
-                    Ok(v) => v,
-                    Err(_) => return Err(Error::from(ErrorKind::InvalidGasLimitValue(raw_value))),
+            network_port,
+            external_network_port: ports.p2p_network_external.unwrap_or(network_port),
+            http_jsonrpc_port: required(
+                ports.http_jsonrpc,
+                "ports.http_jsonrpc (HTTP_JSON_RPC_PORT)",
+            )?,
+            websocket_jsonrpc_port: required(
+                ports.websocket_jsonrpc,
+                "ports.websocket_jsonrpc (WEBSOCKET_JSON_RPC_PORT)",
+            )?,
+            genesis_block_gas_limit: required(
+                config.genesis_block_gas_limit,
+                "genesis_block_gas_limit (GENESIS_BLOCK_GAS_LIMIT)",
+            )?
+            .0,
+        };
+
+        let restart = {
+            let restart = config.restart;
+            let initial_backoff =
+                Duration::from_millis(restart.initial_backoff.unwrap_or(DEFAULT_INITIAL_BACKOFF));
+            let max_backoff =
+                Duration::from_millis(restart.max_backoff.unwrap_or(DEFAULT_MAX_BACKOFF));
+            if initial_backoff > max_backoff {
+                return Err(Error::from(ErrorKind::InvalidConfigValue(
+                    "restart.initial_backoff (RESTART_INITIAL_BACKOFF)".to_owned(),
+                    format!(
+                        "{:?} is larger than max backoff {:?}",
+                        initial_backoff, max_backoff
+                    ),
+                )));
+            }
+
+            RestartParameters {
+                policy: config.restart_policy.unwrap_or(RestartPolicy::Always),
+                initial_backoff,
+                max_backoff,
+                max_restarts: restart.max_count.unwrap_or(DEFAULT_MAX_RESTARTS),
+                window: Duration::from_secs(restart.window.unwrap_or(DEFAULT_RESTART_WINDOW)),
+            }
+        };
+
+        let health = match config.health.port {
+            Some(port) => {
+                let periods = config
+                    .health
+                    .max_block_periods
+                    .unwrap_or(DEFAULT_HEALTH_MAX_BLOCK_PERIODS);
+                if periods == 0 {
+                    return Err(Error::from(ErrorKind::InvalidConfigValue(
+                        "health.max_block_periods (HEALTH_MAX_BLOCK_PERIODS)".to_owned(),
+                        periods.to_string(),
+                    )));
                 }
-            },
+
+                Some(HealthParameters {
+                    port,
+                    max_block_age: consensus_engine.block_period() * periods,
+                })
+            }
+            None => None,
         };
 
-        let public_ip = env::var("PUBLIC_IP").unwrap_or("0.0.0.0".into()).parse()?;
+        let discovery = {
+            let discovery = &config.bootnode_service.discovery;
+            let initial_backoff = Duration::from_millis(
+                discovery
+                    .initial_backoff
+                    .unwrap_or(DEFAULT_DISCOVERY_INITIAL_BACKOFF),
+            );
+            let max_backoff = Duration::from_millis(
+                discovery
+                    .max_backoff
+                    .unwrap_or(DEFAULT_DISCOVERY_MAX_BACKOFF),
+            );
+            if initial_backoff > max_backoff {
+                return Err(Error::from(ErrorKind::InvalidConfigValue(
+                    "bootnode_service.discovery.initial_backoff (DISCOVERY_INITIAL_BACKOFF)"
+                        .to_owned(),
+                    format!(
+                        "{:?} is larger than max backoff {:?}",
+                        initial_backoff, max_backoff
+                    ),
+                )));
+            }
 
-        Ok(Context {
-            first_run: is_first_run()?,
+            DiscoveryParameters {
+                min_peers: discovery.min_peers.unwrap_or(DEFAULT_DISCOVERY_MIN_PEERS),
+                deadline: Duration::from_secs(
+                    discovery.deadline.unwrap_or(DEFAULT_DISCOVERY_DEADLINE),
+                ),
+                initial_backoff,
+                max_backoff,
+                on_failure: discovery
+                    .on_failure
+                    .unwrap_or(DiscoveryFailurePolicy::Continue),
+            }
+        };
+
+        let accept_missing_genesis_inputs_hash = config
+            .bootnode_service
+            .accept_missing_genesis_inputs_hash
+            .unwrap_or(false);
+
+        let peer_refresh_interval = config
+            .bootnode_service
+            .peer_refresh_interval
+            .unwrap_or(DEFAULT_PEER_REFRESH_INTERVAL);
+        if peer_refresh_interval == 0 {
+            return Err(Error::from(ErrorKind::InvalidConfigValue(
+                "bootnode_service.peer_refresh_interval (PEER_REFRESH_INTERVAL)".to_owned(),
+                peer_refresh_interval.to_string(),
+            )));
+        }
 
+        let bootnode_update_interval = config.bootnode_service.update_interval.unwrap_or(10);
+        let bootnode_lease = config
+            .bootnode_service
+            .lease
+            .unwrap_or(bootnode_update_interval * DEFAULT_BOOTNODE_LEASE_INTERVALS);
+        if bootnode_lease <= bootnode_update_interval {
+            return Err(Error::from(ErrorKind::InvalidConfigValue(
+                "bootnode_service.lease (BOOTNODE_SERVICE_LEASE)".to_owned(),
+                format!(
+                    "{}, must be longer than update interval {}",
+                    bootnode_lease, bootnode_update_interval
+                ),
+            )));
+        }
+
+        let public_ip = config
+            .public_ip
+            .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)));
+        let public_host = match config.public_hostname {
+            Some(hostname) => hostname.parse()?,
+            None => NodeHost::Ip(public_ip),
+        };
+        let bootnode_service = {
+            let bootnode_service = config.bootnode_service;
+            let token = secret(
+                bootnode_service.token,
+                bootnode_service.token_file,
+                "bootnode_service.token_file (BOOTNODE_SERVICE_TOKEN_FILE)",
+            )?;
+            let hmac_secret = secret(
+                bootnode_service.hmac_secret,
+                bootnode_service.hmac_secret_file,
+                "bootnode_service.hmac_secret_file (BOOTNODE_SERVICE_HMAC_SECRET_FILE)",
+            )?;
+            let auth = match (token, hmac_secret) {
+                (Some(_), Some(_)) => {
+                    return Err(Error::from(ErrorKind::InvalidConfigValue(
+                        "bootnode_service.token (BOOTNODE_SERVICE_TOKEN)".to_owned(),
+                        "only one of token and hmac_secret may be set".to_owned(),
+                    )));
+                }
+                (Some(token), None) => RegistryAuth::Bearer(token),
+                (None, Some(hmac_secret)) => RegistryAuth::Hmac(hmac_secret),
+                (None, None) => RegistryAuth::None,
+            };
+
+            BootnodeServiceParameters {
+                host: required(
+                    bootnode_service.host,
+                    "bootnode_service.host (BOOTNODE_SERVICE_HOST)",
+                )?,
+                port: required(
+                    bootnode_service.port,
+                    "bootnode_service.port (BOOTNODE_SERVICE_PORT)",
+                )?,
+                tls: bootnode_service.tls.unwrap_or(false),
+                ca_file: bootnode_service.ca_file,
+                auth,
+            }
+        };
+
+        Ok(Context {
             public_ip,
+            public_host,
             network_name,
+            chain,
 
             consensus_engine,
             ethereum_program: consensus_engine.program(),
+            genesis_accounts,
 
             node_type,
+            sealer_passphrase,
             launcher_parameters,
 
-            restart_policy: RestartPolicy::Always,
-
-            bootnode_service_host: env::var("BOOTNODE_SERVICE_HOST")?,
-            bootnode_service_port: env::var("BOOTNODE_SERVICE_PORT")?.parse()?,
-            bootnode_update_interval: Duration::from_secs(
-                env::var("BOOTNODE_SERVICE_UPDATE_INTERVAL")
-                    .unwrap_or("10".into())
-                    .parse()?,
+            restart,
+            client_log: ClientLogParameters {
+                file: config.client_log.file.unwrap_or(false),
+                max_size: config
+                    .client_log
+                    .max_size
+                    .unwrap_or(DEFAULT_CLIENT_LOG_MAX_SIZE),
+                max_files: config
+                    .client_log
+                    .max_files
+                    .unwrap_or(DEFAULT_CLIENT_LOG_MAX_FILES),
+            },
+            shutdown_grace_period: Duration::from_secs(
+                config
+                    .shutdown_grace_period
+                    .unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD),
             ),
+
+            bootnode_service,
+            bootnode_update_interval: Duration::from_secs(bootnode_update_interval),
+            bootnode_lease: Duration::from_secs(bootnode_lease),
+            discovery,
+            peer_refresh_interval: Duration::from_secs(peer_refresh_interval),
+            accept_missing_genesis_inputs_hash,
+
+            health,
         })
     }
 
@@ -3,13 +3,51 @@ error_chain! {
         StdIoError(std::io::Error);
         EnvVarError(std::env::VarError);
         NumParseIntError(std::num::ParseIntError);
+        BoolParseError(std::str::ParseBoolError);
         AddrParseError(std::net::AddrParseError);
         JsonParseError(serde_json::Error);
+        TomlParseError(toml::de::Error);
         EthKeyError(ethkey::Error);
         MnemonicError(emerald::mnemonic::Error);
     }
 
     errors {
+        MissingConfigValue(key: String) {
+            description("Missing configuration value")
+            display("Missing configuration value: {}", key)
+        }
+        InvalidConfigValue(key: String, value: String) {
+            description("Invalid configuration value")
+            display("Invalid configuration value of {}: {}", key, value)
+        }
+        InvalidRestartPolicy(s: String) {
+            description("Invalid restart policy")
+            display("Invalid restart policy: {}", s)
+        }
+        InvalidNodeHost(s: String) {
+            description("Invalid host name")
+            display("Invalid IP address or host name: {}", s)
+        }
+        InvalidEnodeUrl(s: String) {
+            description("Invalid enode URL")
+            display("Invalid enode URL: {}", s)
+        }
+        InvalidDiscoveryFailurePolicy(s: String) {
+            description("Invalid discovery failure policy")
+            display("Invalid discovery failure policy: {}", s)
+        }
+        UnknownHardFork(s: String) {
+            description("Unknown hard fork")
+            display("Unknown hard fork: {}", s)
+        }
+        InvalidForkSchedule(s: String) {
+            description("Invalid hard fork schedule")
+            display("Invalid hard fork schedule: {}", s)
+        }
+        UnsupportedEipTransitions(program: String) {
+            description("Per-EIP transitions are not supported")
+            display("Per-EIP transitions are not supported by {}", program)
+        }
         UnknownNodeType(t: String) {
             description("Unknown node type")
             display("Unknown node type: {}", t)
@@ -34,9 +72,13 @@ error_chain! {
             description("Invalid consensus engine type")
             display("Invalid consensus engine type: {}", s)
         }
-        InvalidGasLimitValue(s: String) {
-            description("Invalid gas limit value")
-            display("Invalid gas limit value: {}", s)
+        InvalidConsensusParameters(engine: String, s: String) {
+            description("Invalid consensus engine parameters")
+            display("Invalid {} consensus engine parameters: {}", engine, s)
+        }
+        InvalidConsensusParameter(engine: String, field: String, reason: String) {
+            description("Invalid consensus engine parameter")
+            display("Invalid {} consensus engine parameter `{}`: {}", engine, field, reason)
         }
         InvalidPrivateKey(s: String) {
             description("Invalid private key")
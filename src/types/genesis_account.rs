@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+
+use ethereum_types::Address;
+use serde_json::Value as JsonValue;
+
+use super::Quantity;
+
+/// pre-funded account in genesis block
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisAccount {
+    pub address: Address,
+    pub balance: Quantity,
+    #[serde(default)]
+    pub nonce: Option<Quantity>,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+}
+
+impl GenesisAccount {
+    pub fn new(address: Address, balance: Quantity) -> GenesisAccount {
+        GenesisAccount {
+            address,
+            balance,
+            nonce: None,
+            code: None,
+            storage: BTreeMap::new(),
+        }
+    }
+
+    /// account fields shared by Parity `accounts` and go-ethereum `alloc`
+    pub fn to_json(&self) -> JsonValue {
+        let mut account = json!({
+            "balance": format!("0x{:x}", self.balance.0),
+        });
+
+        let fields = account.as_object_mut().expect("account is an object; qed");
+        if let Some(nonce) = self.nonce {
+            fields.insert("nonce".into(), json!(format!("0x{:x}", nonce.0)));
+        }
+        if let Some(ref code) = self.code {
+            fields.insert("code".into(), json!(code));
+        }
+        if !self.storage.is_empty() {
+            fields.insert("storage".into(), json!(self.storage));
+        }
+
+        account
+    }
+}
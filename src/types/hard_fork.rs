@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::{Error, ErrorKind};
+
+/// protocol upgrades of Ethereum, in activation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HardFork {
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+}
+
+impl HardFork {
+    pub fn all() -> Vec<HardFork> {
+        vec![
+            HardFork::Homestead,
+            HardFork::TangerineWhistle,
+            HardFork::SpuriousDragon,
+            HardFork::Byzantium,
+            HardFork::Constantinople,
+            HardFork::Petersburg,
+            HardFork::Istanbul,
+        ]
+    }
+}
+
+impl ToString for HardFork {
+    fn to_string(&self) -> String {
+        match self {
+            HardFork::Homestead => "homestead".to_owned(),
+            HardFork::TangerineWhistle => "tangerine-whistle".to_owned(),
+            HardFork::SpuriousDragon => "spurious-dragon".to_owned(),
+            HardFork::Byzantium => "byzantium".to_owned(),
+            HardFork::Constantinople => "constantinople".to_owned(),
+            HardFork::Petersburg => "petersburg".to_owned(),
+            HardFork::Istanbul => "istanbul".to_owned(),
+        }
+    }
+}
+
+impl FromStr for HardFork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<HardFork, Error> {
+        HardFork::all()
+            .into_iter()
+            .find(|fork| fork.to_string() == s.to_lowercase())
+            .ok_or_else(|| Error::from(ErrorKind::UnknownHardFork(s.to_owned())))
+    }
+}
+
+/// activation block of each hard fork
+#[derive(Debug, Clone)]
+pub struct ForkSchedule {
+    blocks: BTreeMap<HardFork, u64>,
+
+    /// extra Parity transitions keyed by EIP name, e.g. `eip1283` for `eip1283Transition`
+    pub eip_transitions: BTreeMap<String, u64>,
+}
+
+impl ForkSchedule {
+    /// forks up to `level` activate at genesis, `blocks` overrides or schedules single forks
+    pub fn new(
+        level: HardFork,
+        blocks: BTreeMap<HardFork, u64>,
+        eip_transitions: BTreeMap<String, u64>,
+    ) -> Result<ForkSchedule, Error> {
+        let mut schedule = BTreeMap::new();
+        for fork in HardFork::all() {
+            if fork <= level {
+                schedule.insert(fork, 0);
+            }
+        }
+        schedule.extend(blocks);
+
+        // enabled forks must be contiguous and activate in order
+        let mut previous: Option<(HardFork, u64)> = None;
+        let mut first_disabled: Option<HardFork> = None;
+        for fork in HardFork::all() {
+            let block = match schedule.get(&fork) {
+                Some(&block) => block,
+                None => {
+                    first_disabled = first_disabled.or(Some(fork));
+                    continue;
+                }
+            };
+
+            if let Some(disabled) = first_disabled {
+                return Err(Error::from(ErrorKind::InvalidForkSchedule(format!(
+                    "{} requires {}",
+                    fork.to_string(),
+                    disabled.to_string()
+                ))));
+            }
+            if let Some((previous_fork, previous_block)) = previous {
+                if block < previous_block {
+                    return Err(Error::from(ErrorKind::InvalidForkSchedule(format!(
+                        "{} at block {} is before {} at block {}",
+                        fork.to_string(),
+                        block,
+                        previous_fork.to_string(),
+                        previous_block
+                    ))));
+                }
+            }
+            previous = Some((fork, block));
+        }
+
+        for name in eip_transitions.keys() {
+            let is_eip_name = name.starts_with("eip")
+                && name.len() > 3
+                && name[3..].chars().all(|c| c.is_ascii_alphanumeric());
+            if !is_eip_name {
+                return Err(Error::from(ErrorKind::InvalidForkSchedule(format!(
+                    "invalid EIP transition name: {}",
+                    name
+                ))));
+            }
+        }
+
+        Ok(ForkSchedule {
+            blocks: schedule,
+            eip_transitions,
+        })
+    }
+
+    /// activation block of `fork`, `None` if it is never activated
+    pub fn activation(&self, fork: HardFork) -> Option<u64> {
+        self.blocks.get(&fork).cloned()
+    }
+
+    /// activation block of `fork`, `u64::max_value()` if it is never activated
+    pub fn transition(&self, fork: HardFork) -> u64 {
+        self.activation(fork).unwrap_or(u64::max_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(level: HardFork, blocks: Vec<(HardFork, u64)>) -> Result<ForkSchedule, Error> {
+        ForkSchedule::new(level, blocks.into_iter().collect(), BTreeMap::new())
+    }
+
+    #[test]
+    fn forks_up_to_level_activate_at_genesis() {
+        let forks = schedule(HardFork::Byzantium, vec![]).unwrap();
+        assert_eq!(forks.activation(HardFork::Homestead), Some(0));
+        assert_eq!(forks.activation(HardFork::Byzantium), Some(0));
+        assert_eq!(forks.activation(HardFork::Constantinople), None);
+        assert_eq!(forks.transition(HardFork::Istanbul), u64::max_value());
+    }
+
+    #[test]
+    fn later_forks_are_scheduled_in_order() {
+        let forks = schedule(
+            HardFork::Byzantium,
+            vec![
+                (HardFork::Constantinople, 100),
+                (HardFork::Petersburg, 100),
+                (HardFork::Istanbul, 200),
+            ],
+        )
+        .unwrap();
+        assert_eq!(forks.activation(HardFork::Constantinople), Some(100));
+        assert_eq!(forks.activation(HardFork::Istanbul), Some(200));
+    }
+
+    #[test]
+    fn fork_before_previous_fork_is_rejected() {
+        let result = schedule(
+            HardFork::Byzantium,
+            vec![(HardFork::Constantinople, 200), (HardFork::Petersburg, 100)],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn fork_after_disabled_fork_is_rejected() {
+        let result = schedule(HardFork::Byzantium, vec![(HardFork::Petersburg, 100)]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_eip_transition_name_is_rejected() {
+        let transitions = vec![("eip-1283".to_owned(), 10)].into_iter().collect();
+        assert!(ForkSchedule::new(HardFork::Byzantium, BTreeMap::new(), transitions).is_err());
+
+        let transitions = vec![("eip1283".to_owned(), 10)].into_iter().collect();
+        assert!(ForkSchedule::new(HardFork::Byzantium, BTreeMap::new(), transitions).is_ok());
+    }
+}
@@ -1,16 +1,27 @@
+use std::fmt;
 use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
 
 use ethereum_types::U256;
 
+mod config;
 mod consensus_engine;
 mod context;
 mod error;
+mod genesis_account;
+mod hard_fork;
 mod node_type;
+mod quantity;
 
-pub use self::consensus_engine::ConsensusEngine;
+pub use self::config::LauncherConfig;
+pub use self::consensus_engine::{ConsensusEngine, ETHASH_DURATION_LIMIT};
 pub use self::context::Context;
 pub use self::error::{Error, ErrorKind};
+pub use self::genesis_account::GenesisAccount;
+pub use self::hard_fork::{ForkSchedule, HardFork};
 pub use self::node_type::NodeType;
+pub use self::quantity::Quantity;
 
 #[derive(Debug, Clone, Copy)]
 pub enum EthereumProgram {
@@ -18,36 +29,422 @@ pub enum EthereumProgram {
     GoEthereum,
 }
 
+#[derive(Debug, Clone)]
+pub struct ChainParameters {
+    pub name: String,
+    pub network_id: u64,
+    pub chain_id: u64,
+    pub forks: ForkSchedule,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct LauncherParameters {
     pub network_port: u16,
+    /// P2P port advertised in enode URL, the same as `network_port` unless it is mapped
+    pub external_network_port: u16,
     pub http_jsonrpc_port: u16,
     pub websocket_jsonrpc_port: u16,
     pub genesis_block_gas_limit: U256,
 }
 
-#[derive(Debug, Copy, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum RestartPolicy {
     No,
     Always,
     OnFailure,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FromStr for RestartPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<RestartPolicy, Error> {
+        match s.to_lowercase().as_ref() {
+            "no" => Ok(RestartPolicy::No),
+            "always" => Ok(RestartPolicy::Always),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            _ => Err(Error::from(ErrorKind::InvalidRestartPolicy(s.to_owned()))),
+        }
+    }
+}
+
+/// what to do if not enough static enodes are discovered before deadline
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiscoveryFailurePolicy {
+    Fail,
+    Continue,
+}
+
+impl FromStr for DiscoveryFailurePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DiscoveryFailurePolicy, Error> {
+        match s.to_lowercase().as_ref() {
+            "fail" => Ok(DiscoveryFailurePolicy::Fail),
+            "continue" => Ok(DiscoveryFailurePolicy::Continue),
+            _ => Err(Error::from(ErrorKind::InvalidDiscoveryFailurePolicy(
+                s.to_owned(),
+            ))),
+        }
+    }
+}
+
+/// retry strategy of fetching static enodes from bootnode service
+#[derive(Debug, Clone, Copy)]
+pub struct DiscoveryParameters {
+    /// static enodes required before Ethereum client is started
+    pub min_peers: usize,
+
+    /// total time to retry
+    pub deadline: Duration,
+
+    /// delay before the first retry, doubled on every retry
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+
+    pub on_failure: DiscoveryFailurePolicy,
+}
+
+/// how requests to bootnode service are authenticated
+#[derive(Debug, Clone)]
+pub enum RegistryAuth {
+    None,
+    /// `Authorization: Bearer <token>`
+    Bearer(Passphrase),
+    /// HMAC-SHA256 of request with shared network secret
+    Hmac(Passphrase),
+}
+
+/// where and how to reach bootnode service
+#[derive(Debug, Clone)]
+pub struct BootnodeServiceParameters {
+    pub host: String,
+    pub port: u16,
+
+    /// HTTPS instead of HTTP
+    pub tls: bool,
+
+    /// PEM bundle of CA certificates trusted in addition to system ones
+    pub ca_file: Option<String>,
+
+    pub auth: RegistryAuth,
+}
+
+/// how and how often the Ethereum client is restarted after it exits
+#[derive(Debug, Clone, Copy)]
+pub struct RestartParameters {
+    pub policy: RestartPolicy,
+
+    /// delay before the first restart, doubled on every consecutive restart
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+
+    /// restarts allowed within `window` before giving up
+    pub max_restarts: usize,
+    pub window: Duration,
+}
+
+/// whether and how Ethereum client output is written into a log file
+#[derive(Debug, Clone, Copy)]
+pub struct ClientLogParameters {
+    pub file: bool,
+    pub max_size: u64,
+    pub max_files: usize,
+}
+
+/// HTTP server of `/healthz`, `/readyz` and `/metrics`
+#[derive(Debug, Clone, Copy)]
+pub struct HealthParameters {
+    pub port: u16,
+
+    /// a miner is not ready if its latest block is older than this
+    pub max_block_age: Duration,
+}
+
+/// passphrase of sealer key file, hidden from debug output
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Passphrase(String);
+
+impl Passphrase {
+    pub fn new(passphrase: String) -> Passphrase {
+        Passphrase(passphrase)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for Passphrase {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Passphrase, Error> {
+        Ok(Passphrase::new(s.to_owned()))
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Passphrase(<hidden>)")
+    }
+}
+
+/// host of enode URL, host names are resolved before they are handed to Ethereum client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NodeHost {
+    Ip(IpAddr),
+    Name(String),
+}
+
+impl NodeHost {
+    pub fn is_unspecified(&self) -> bool {
+        match self {
+            NodeHost::Ip(addr) => addr.is_unspecified(),
+            NodeHost::Name(_) => false,
+        }
+    }
+}
+
+impl FromStr for NodeHost {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<NodeHost, Error> {
+        if let Ok(addr) = s.parse() {
+            return Ok(NodeHost::Ip(addr));
+        }
+
+        let is_label = |label: &str| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        let name = s.trim_end_matches('.');
+        if name.is_empty() || name.len() > 253 || !name.split('.').all(is_label) {
+            return Err(Error::from(ErrorKind::InvalidNodeHost(s.to_owned())));
+        }
+        Ok(NodeHost::Name(name.to_lowercase()))
+    }
+}
+
+impl fmt::Display for NodeHost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeHost::Ip(addr) => write!(f, "{}", addr),
+            NodeHost::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EthereumNodeUrl {
     pub node_id: String,
-    pub addr: IpAddr,
+    pub host: NodeHost,
     pub port: u16,
+
+    /// UDP port of node discovery, if it is not the P2P port
+    pub discovery_port: Option<u16>,
+}
+
+impl EthereumNodeUrl {
+    /// the same node at resolved address
+    pub fn with_addr(&self, addr: IpAddr) -> EthereumNodeUrl {
+        EthereumNodeUrl {
+            host: NodeHost::Ip(addr),
+            ..self.clone()
+        }
+    }
+
+    /// Parity does not accept `discport`, it discovers nodes on their P2P port
+    pub fn without_discovery_port(&self) -> EthereumNodeUrl {
+        EthereumNodeUrl {
+            discovery_port: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl FromStr for EthereumNodeUrl {
+    type Err = Error;
+
+    /// `enode://<node ID>@<IP or host name>:<port>[?discport=<port>]`
+    fn from_str(s: &str) -> Result<EthereumNodeUrl, Error> {
+        use url::{Host, Url};
+
+        let invalid = || Error::from(ErrorKind::InvalidEnodeUrl(s.to_owned()));
+        let url = Url::parse(s).map_err(|_| invalid())?;
+        if url.scheme() != "enode" || url.username().is_empty() {
+            return Err(invalid());
+        }
+
+        let host = match url.host() {
+            Some(Host::Ipv4(addr)) => NodeHost::Ip(IpAddr::V4(addr)),
+            Some(Host::Ipv6(addr)) => NodeHost::Ip(IpAddr::V6(addr)),
+            Some(Host::Domain(name)) => name.parse()?,
+            None => return Err(invalid()),
+        };
+        let discovery_port = match url.query_pairs().find(|(key, _)| key == "discport") {
+            Some((_, value)) => Some(value.parse().map_err(|_| invalid())?),
+            None => None,
+        };
+
+        Ok(EthereumNodeUrl {
+            node_id: url.username().to_owned(),
+            host,
+            port: url.port().ok_or_else(invalid)?,
+            discovery_port,
+        })
+    }
+}
+
+/// registration of a node to bootnode service, `POST /`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnodeInfo {
+    /// node ID of enode URL
+    pub enode: String,
+    pub port: u16,
+    pub ip: String,
+    /// public IP or host name
+    pub public_ip: String,
+    /// UDP port of node discovery, if it is not `port`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discovery_port: Option<u16>,
+    pub network: String,
+    pub network_id: u64,
+    pub chain_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_inputs_hash: Option<String>,
+    pub miner: bool,
+
+    /// lease in seconds, registration expires if it is not renewed within lease
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+}
+
+/// deregistration of a node from bootnode service, `DELETE /`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnodeDeregistration {
+    /// node ID of enode URL
+    pub enode: String,
+    pub network: String,
+}
+
+/// response of bootnode service to registration and deregistration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryResponse {
+    pub ok: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// lease in seconds granted to registration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
 }
 
 impl ToString for EthereumNodeUrl {
     fn to_string(&self) -> String {
-        format!(
-            "enode://{}@{}:{}",
-            self.node_id,
-            self.addr.to_string(),
-            self.port
-        )
+        let host = match self.host {
+            NodeHost::Ip(IpAddr::V6(addr)) => format!("[{}]", addr),
+            ref host => host.to_string(),
+        };
+        let mut url = format!("enode://{}@{}:{}", self.node_id, host, self.port);
+        if let Some(discovery_port) = self.discovery_port {
+            url.push_str(format!("?discport={}", discovery_port).as_str());
+        }
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODE_ID: &str = "6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0";
+
+    #[test]
+    fn parse_node_host() {
+        assert_eq!(
+            "10.3.58.6".parse::<NodeHost>().unwrap(),
+            NodeHost::Ip("10.3.58.6".parse().unwrap())
+        );
+        assert_eq!(
+            "::1".parse::<NodeHost>().unwrap(),
+            NodeHost::Ip("::1".parse().unwrap())
+        );
+        assert_eq!(
+            "Node-0.Example.com.".parse::<NodeHost>().unwrap(),
+            NodeHost::Name("node-0.example.com".to_owned())
+        );
+        assert!("0.0.0.0".parse::<NodeHost>().unwrap().is_unspecified());
+    }
+
+    #[test]
+    fn reject_invalid_node_host() {
+        assert!("".parse::<NodeHost>().is_err());
+        assert!("-node.example.com".parse::<NodeHost>().is_err());
+        assert!("node..example.com".parse::<NodeHost>().is_err());
+        assert!("node_0.example.com".parse::<NodeHost>().is_err());
+        assert!(format!("{}.com", "a".repeat(64))
+            .parse::<NodeHost>()
+            .is_err());
+    }
+
+    #[test]
+    fn parse_enode_url_with_host_name() {
+        let url = format!("enode://{}@node0.example.com:30303", NODE_ID);
+        let enode_url = url.parse::<EthereumNodeUrl>().unwrap();
+        assert_eq!(enode_url.node_id, NODE_ID);
+        assert_eq!(
+            enode_url.host,
+            NodeHost::Name("node0.example.com".to_owned())
+        );
+        assert_eq!(enode_url.port, 30303);
+        assert_eq!(enode_url.discovery_port, None);
+        assert_eq!(enode_url.to_string(), url);
+    }
+
+    #[test]
+    fn parse_enode_url_with_discovery_port() {
+        let url = format!("enode://{}@10.3.58.6:30303?discport=30301", NODE_ID);
+        let enode_url = url.parse::<EthereumNodeUrl>().unwrap();
+        assert_eq!(enode_url.host, NodeHost::Ip("10.3.58.6".parse().unwrap()));
+        assert_eq!(enode_url.discovery_port, Some(30301));
+        assert_eq!(enode_url.to_string(), url);
+        assert_eq!(
+            enode_url.without_discovery_port().to_string(),
+            format!("enode://{}@10.3.58.6:30303", NODE_ID)
+        );
+    }
+
+    #[test]
+    fn parse_enode_url_with_ipv6_address() {
+        let url = format!("enode://{}@[::1]:30303", NODE_ID);
+        let enode_url = url.parse::<EthereumNodeUrl>().unwrap();
+        assert_eq!(enode_url.host, NodeHost::Ip("::1".parse().unwrap()));
+        assert_eq!(enode_url.to_string(), url);
+    }
+
+    #[test]
+    fn reject_invalid_enode_url() {
+        assert!("enode://10.3.58.6:30303"
+            .parse::<EthereumNodeUrl>()
+            .is_err());
+        assert!(format!("http://{}@10.3.58.6:30303", NODE_ID)
+            .parse::<EthereumNodeUrl>()
+            .is_err());
+        assert!(format!("enode://{}@10.3.58.6", NODE_ID)
+            .parse::<EthereumNodeUrl>()
+            .is_err());
+        assert!(format!("enode://{}@10.3.58.6:30303?discport=udp", NODE_ID)
+            .parse::<EthereumNodeUrl>()
+            .is_err());
+        assert!(format!("enode://{}@node_0.example.com:30303", NODE_ID)
+            .parse::<EthereumNodeUrl>()
+            .is_err());
     }
 }
@@ -5,6 +5,9 @@ use emerald::mnemonic::{self, HDPath, Language, Mnemonic};
 
 use super::{Error, ErrorKind};
 
+/// HD path of validator keys, index of validator is appended
+const VALIDATOR_HD_PATH: &'static str = "m/44'/60'/0'/0";
+
 #[derive(Debug, Clone)]
 pub enum NodeType {
     Miner {
@@ -44,25 +47,40 @@ impl NodeType {
     }
 
     pub fn validator_keypairs(&self) -> Result<Vec<KeyPair>, Error> {
-        let (miner_count, sealer_mnemonic) = match self {
+        let (miner_count, sealer_mnemonic) = self.sealer_parameters();
+        keypair_from_sealer_mnemonic(sealer_mnemonic, VALIDATOR_HD_PATH, miner_count)
+    }
+
+    /// derive `count` accounts from sealer mnemonic under `hd_path`
+    pub fn derived_accounts(&self, hd_path: &str, count: usize) -> Result<Vec<Address>, Error> {
+        let (_, sealer_mnemonic) = self.sealer_parameters();
+        Ok(
+            keypair_from_sealer_mnemonic(sealer_mnemonic, hd_path, count)?
+                .iter()
+                .map(KeyPair::address)
+                .collect(),
+        )
+    }
+
+    fn sealer_parameters(&self) -> (usize, &String) {
+        match self {
             NodeType::Miner {
                 miner_count,
                 sealer_mnemonic,
                 ..
-            } => (miner_count, sealer_mnemonic),
+            } => (*miner_count, sealer_mnemonic),
             NodeType::Transactor {
                 miner_count,
                 sealer_mnemonic,
-            } => (miner_count, sealer_mnemonic),
-        };
-
-        keypair_from_sealer_mnemonic(&sealer_mnemonic, *miner_count)
+            } => (*miner_count, sealer_mnemonic),
+        }
     }
 }
 
 fn keypair_from_sealer_mnemonic(
     sealer_mnemonic: &String,
-    sealer_count: usize,
+    hd_path: &str,
+    count: usize,
 ) -> Result<Vec<KeyPair>, Error> {
     let mnemonic = match Mnemonic::try_from(Language::English, sealer_mnemonic) {
         Ok(m) => m,
@@ -74,9 +92,9 @@ fn keypair_from_sealer_mnemonic(
     };
 
     let mut keypairs = Vec::new();
-    for i in 0..sealer_count {
+    for i in 0..count {
         let seed = mnemonic.seed("");
-        let raw_path = format!("m/44'/60'/0'/0/{}", i);
+        let raw_path = format!("{}/{}", hd_path.trim_end_matches('/'), i);
         let path = match HDPath::try_from(raw_path.as_str()) {
             Ok(path) => path,
             Err(_err) => return Err(Error::from(ErrorKind::InvalidHDPath(raw_path))),
@@ -0,0 +1,131 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ethereum_types::U256;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+
+/// unsigned integer which can be written as JSON number, decimal string or `0x` prefixed hex string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantity(pub U256);
+
+impl FromStr for Quantity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Quantity, String> {
+        let s = s.trim();
+        if s.starts_with("0x") || s.starts_with("0X") {
+            let hex = &s[2..];
+            if hex.is_empty() || hex.len() > 64 {
+                return Err(format!("invalid hex quantity: {}", s));
+            }
+            U256::from_str(hex)
+                .map(Quantity)
+                .map_err(|_| format!("invalid hex quantity: {}", s))
+        } else {
+            U256::from_dec_str(s)
+                .map(Quantity)
+                .map_err(|_| format!("invalid decimal quantity: {}", s))
+        }
+    }
+}
+
+impl Quantity {
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.0 > U256::from(u64::max_value()) {
+            None
+        } else {
+            Some(self.0.as_u64())
+        }
+    }
+}
+
+impl From<u64> for Quantity {
+    fn from(value: u64) -> Quantity {
+        Quantity(U256::from(value))
+    }
+}
+
+struct QuantityVisitor;
+
+impl<'de> Visitor<'de> for QuantityVisitor {
+    type Value = Quantity;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an unsigned integer, a decimal string or a 0x prefixed hex string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Quantity, E> {
+        Ok(Quantity::from(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Quantity, E> {
+        if value < 0 {
+            return Err(E::invalid_value(de::Unexpected::Signed(value), &self));
+        }
+        Ok(Quantity::from(value as u64))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Quantity, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Quantity, D::Error> {
+        deserializer.deserialize_any(QuantityVisitor)
+    }
+}
+
+/// deserialize a quantity which must fit in `u64`
+pub fn deserialize_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    use serde::de::Error;
+
+    let value = Quantity::deserialize(deserializer)?;
+    value
+        .to_u64()
+        .ok_or_else(|| D::Error::custom(format!("{} is out of range", value.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_decimal_and_hex() {
+        assert_eq!("8000000".parse(), Ok(Quantity::from(8_000_000)));
+        assert_eq!("0x7a1200".parse(), Ok(Quantity::from(8_000_000)));
+        assert_eq!("0X7A1200".parse(), Ok(Quantity::from(8_000_000)));
+        assert_eq!(" 42 ".parse(), Ok(Quantity::from(42)));
+    }
+
+    #[test]
+    fn reject_invalid_quantities() {
+        assert!("0x".parse::<Quantity>().is_err());
+        assert!("0xzz".parse::<Quantity>().is_err());
+        assert!("-1".parse::<Quantity>().is_err());
+        assert!("12ab".parse::<Quantity>().is_err());
+        assert!(format!("0x1{}", "0".repeat(64))
+            .parse::<Quantity>()
+            .is_err());
+    }
+
+    #[test]
+    fn deserialize_json_number_and_strings() {
+        let values: Vec<Quantity> = serde_json::from_str(r#"[16, "16", "0x10"]"#).unwrap();
+        assert_eq!(values, vec![Quantity::from(16); 3]);
+        assert!(serde_json::from_str::<Quantity>("-16").is_err());
+    }
+
+    #[test]
+    fn deserialize_u64_rejects_overflow() {
+        #[derive(Deserialize)]
+        struct Limit {
+            #[serde(deserialize_with = "deserialize_u64")]
+            value: u64,
+        }
+
+        let limit: Limit = serde_json::from_str(r#"{"value": "0xffffffffffffffff"}"#).unwrap();
+        assert_eq!(limit.value, u64::max_value());
+        assert!(serde_json::from_str::<Limit>(r#"{"value": "0x10000000000000000"}"#).is_err());
+    }
+}